@@ -0,0 +1,50 @@
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use cxxgen::consts::{ORBIT_DEF_NAME, ORBIT_DLL_NAME, ORBIT_HEADER_PATH, ORBIT_IMPLIB_NAME};
+use cxxgen::exports::{read_exports, Export};
+use cxxgen::header::generate_header;
+use cxxgen::implib::{generate_def, write_implib};
+use cxxgen::listeners::read_listeners;
+
+fn main() -> Result<()> {
+    println!("cargo:rerun-if-changed=src/api.rs");
+    println!("cargo:rerun-if-changed=src/types.rs");
+
+    let exports = read_exports(&fs::read_to_string("src/api.rs")?)?;
+    let listeners = read_listeners(&fs::read_to_string("src/types.rs")?)?;
+
+    let header = generate_header(&exports, &listeners)?;
+    let def = generate_def(ORBIT_DLL_NAME, &exports);
+
+    // OUT_DIR is target/<triple>/<profile>/build/<crate>-<hash>/out, so the SDK
+    // files are also copied next to the built DLL where test games can find them.
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    let artifact_dir = out_dir.ancestors().nth(3).unwrap_or(&out_dir).to_owned();
+
+    for dir in &[out_dir, artifact_dir] {
+        write_sdk(dir, &header, &def, &exports)?;
+    }
+
+    Ok(())
+}
+
+fn write_sdk(dir: &Path, header: &str, def: &str, exports: &[Export]) -> Result<()> {
+    let header_path = dir.join(ORBIT_HEADER_PATH);
+
+    if let Some(parent) = header_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    fs::write(header_path, header)?;
+    fs::write(dir.join(ORBIT_DEF_NAME), def)?;
+    write_implib(
+        &mut File::create(dir.join(ORBIT_IMPLIB_NAME))?,
+        ORBIT_DLL_NAME,
+        exports,
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,4 @@
+pub const ORBIT_DLL_NAME: &str = "ubiorbitapi_r2.dll";
+pub const ORBIT_HEADER_PATH: &str = "include/mg/orbitclient.h";
+pub const ORBIT_DEF_NAME: &str = "ubiorbitapi_r2.def";
+pub const ORBIT_IMPLIB_NAME: &str = "ubiorbitapi_r2.lib";
@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use syn::{Item, Lit, Meta, NestedMeta};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Export {
+    pub symbol: String,
+    pub function: String,
+    pub args: usize,
+}
+
+/// Collects every `#[cxxabi(name = ...)]` function of a source file, with the
+/// number of C++ arguments it takes (`this` excluded).
+pub fn read_exports(source: &str) -> Result<Vec<Export>> {
+    let file = syn::parse_file(source)?;
+    let mut exports = Vec::new();
+
+    for item in file.items {
+        let func = match item {
+            Item::Fn(func) => func,
+            _ => continue,
+        };
+
+        for attr in func
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("cxxabi"))
+        {
+            let mut symbol = None;
+            let mut ctor = false;
+
+            if let Meta::List(list) = attr.parse_meta()? {
+                for nested in list.nested {
+                    if let NestedMeta::Meta(Meta::NameValue(value)) = nested {
+                        match value.lit {
+                            Lit::Str(name) if value.path.is_ident("name") => {
+                                symbol = Some(name.value());
+                            }
+                            Lit::Bool(flag) if value.path.is_ident("ctor") => {
+                                ctor = flag.value;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            let function = func.sig.ident.to_string();
+            let symbol = symbol.ok_or_else(|| anyhow!("{} has no cxxabi name!", function))?;
+            let inputs = func.sig.inputs.len();
+            let args = if ctor {
+                inputs
+            } else {
+                inputs.saturating_sub(1)
+            };
+
+            exports.push(Export {
+                symbol,
+                function,
+                args,
+            });
+        }
+    }
+
+    Ok(exports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_annotated_functions() {
+        let source = r#"
+            #[inline(never)]
+            #[cxxabi(name = "??0OrbitClient@orbitclient@mg@@QAE@XZ", ctor = true)]
+            fn orbit_client_ctor() -> *const OrbitClient {}
+
+            #[cxxabi(name = "?Read@SavegameReader@orbitclient@mg@@QAEXIPAVISavegameReadListener@23@IPAXI@Z", ctor = false)]
+            fn savegame_reader_read(reader: *const SavegameReader, request_id: u32, listener: *const ISavegameReadListener, offset: u32, buffer: *mut c_char, number_of_bytes: u32) {}
+
+            fn not_exported(client: *const OrbitClient) {}
+        "#;
+
+        let exports = read_exports(source).unwrap();
+
+        assert_eq!(
+            exports,
+            vec![
+                Export {
+                    symbol: "??0OrbitClient@orbitclient@mg@@QAE@XZ".to_owned(),
+                    function: "orbit_client_ctor".to_owned(),
+                    args: 0,
+                },
+                Export {
+                    symbol: "?Read@SavegameReader@orbitclient@mg@@QAEXIPAVISavegameReadListener@23@IPAXI@Z".to_owned(),
+                    function: "savegame_reader_read".to_owned(),
+                    args: 5,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_name_is_an_error() {
+        let source = r#"
+            #[cxxabi(ctor = true)]
+            fn orbit_client_ctor() {}
+        "#;
+
+        assert!(read_exports(source).is_err());
+    }
+}
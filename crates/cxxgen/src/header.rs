@@ -0,0 +1,202 @@
+use anyhow::{anyhow, Result};
+use msvc_demangler::DemangleFlags;
+
+use crate::exports::Export;
+use crate::listeners::Listener;
+
+const NAMESPACE: &str = "mg::orbitclient::";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Method {
+    class: String,
+    declaration: String,
+}
+
+fn parse_method(export: &Export) -> Result<Method> {
+    let demangled = msvc_demangler::demangle(&export.symbol, DemangleFlags::llvm())
+        .map_err(|err| anyhow!("{}: {}", export.symbol, err))?;
+
+    let unsupported = || anyhow!("Unsupported export: {}", demangled);
+
+    let rest = demangled.strip_prefix("public: ").ok_or_else(unsupported)?;
+    let (ret, rest) = match rest.strip_prefix("__thiscall ") {
+        Some(rest) => ("", rest),
+        None => rest.split_once(" __thiscall ").ok_or_else(unsupported)?,
+    };
+
+    let open = rest.find('(').ok_or_else(unsupported)?;
+    let (qualified, params) = rest.split_at(open);
+    let (class, method) = qualified
+        .strip_prefix(NAMESPACE)
+        .and_then(|name| name.split_once("::"))
+        .ok_or_else(unsupported)?;
+
+    let params = params
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .replace(&format!("class {}", NAMESPACE), "");
+    let params = if params == "void" {
+        String::new()
+    } else {
+        params
+    };
+
+    let count = if params.is_empty() {
+        0
+    } else {
+        params.split(", ").count()
+    };
+
+    if count != export.args {
+        return Err(anyhow!(
+            "{} takes {} arguments, but {} expects {}",
+            export.function,
+            export.args,
+            demangled,
+            count
+        ));
+    }
+
+    let declaration = match ret {
+        "" => format!("{}({});", method, params),
+        ret if ret.ends_with('*') => format!("{}{}({});", ret, method, params),
+        ret => format!("{} {}({});", ret, method, params),
+    };
+
+    Ok(Method {
+        class: class.to_owned(),
+        declaration,
+    })
+}
+
+/// Renders `mg/orbitclient.h` for the given exports and listener interfaces.
+pub fn generate_header(exports: &[Export], listeners: &[Listener]) -> Result<String> {
+    let methods = exports
+        .iter()
+        .map(parse_method)
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut classes: Vec<&str> = Vec::new();
+
+    for method in &methods {
+        if !classes.contains(&method.class.as_str()) {
+            classes.push(&method.class);
+        }
+    }
+
+    let mut header = String::new();
+
+    header.push_str("// Generated from the #[cxxabi] exports in src/api.rs and the listener\n");
+    header.push_str("// interfaces in src/types.rs. Do not edit.\n");
+    header.push_str("#pragma once\n\n");
+    header.push_str("namespace mg {\nnamespace orbitclient {\n\n");
+
+    for class in &classes {
+        header.push_str(&format!("class {};\n", class));
+    }
+
+    for listener in listeners {
+        let args = listener
+            .args
+            .iter()
+            .map(|(name, ty)| match ty.ends_with('*') {
+                true => format!("{}{}", ty, name),
+                false => format!("{} {}", ty, name),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        header.push_str(&format!(
+            "\nclass {} {{\npublic:\n    virtual void {}Callback({}) = 0;\n}};\n",
+            listener.name,
+            &listener.name[1..],
+            args
+        ));
+    }
+
+    for class in &classes {
+        header.push_str(&format!(
+            "\nclass __declspec(dllimport) {} {{\npublic:\n",
+            class
+        ));
+
+        for method in methods.iter().filter(|method| method.class == *class) {
+            header.push_str(&format!("    {}\n", method.declaration));
+        }
+
+        header.push_str("};\n");
+    }
+
+    header.push_str("\n} // namespace orbitclient\n} // namespace mg\n");
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(symbol: &str, args: usize) -> Export {
+        Export {
+            symbol: symbol.to_owned(),
+            function: "function".to_owned(),
+            args,
+        }
+    }
+
+    #[test]
+    fn parses_methods() {
+        let ctor = parse_method(&export("??0OrbitClient@orbitclient@mg@@QAE@XZ", 0)).unwrap();
+        let list = parse_method(&export(
+            "?GetSavegameList@OrbitClient@orbitclient@mg@@QAEXIPAVIGetSavegameListListener@23@I@Z",
+            3,
+        ))
+        .unwrap();
+        let name =
+            parse_method(&export("?GetName@SavegameInfo@orbitclient@mg@@QAEPBGXZ", 0)).unwrap();
+
+        assert_eq!(ctor.class, "OrbitClient");
+        assert_eq!(ctor.declaration, "OrbitClient();");
+        assert_eq!(
+            list.declaration,
+            "void GetSavegameList(unsigned int, IGetSavegameListListener *, unsigned int);"
+        );
+        assert_eq!(name.class, "SavegameInfo");
+        assert_eq!(name.declaration, "unsigned short const *GetName();");
+    }
+
+    #[test]
+    fn argument_count_mismatch_is_an_error() {
+        let update = export("?Update@OrbitClient@orbitclient@mg@@QAEXXZ", 1);
+
+        assert!(parse_method(&update).is_err());
+    }
+
+    #[test]
+    fn generates_header() {
+        let exports = vec![
+            export("??0OrbitClient@orbitclient@mg@@QAE@XZ", 0),
+            export("?RemoveSavegame@OrbitClient@orbitclient@mg@@QAEXIPAVIRemoveSavegameListener@23@II@Z", 4),
+        ];
+        let listeners = vec![Listener {
+            name: "IRemoveSavegameListener".to_owned(),
+            args: vec![
+                ("request_id".to_owned(), "unsigned int".to_owned()),
+                ("removed".to_owned(), "bool".to_owned()),
+            ],
+        }];
+
+        let header = generate_header(&exports, &listeners).unwrap();
+
+        assert!(header.contains("class OrbitClient;\n"));
+        assert!(header.contains(
+            "class IRemoveSavegameListener {\npublic:\n    virtual void \
+             RemoveSavegameListenerCallback(unsigned int request_id, bool removed) = 0;\n};\n"
+        ));
+        assert!(header.contains(
+            "class __declspec(dllimport) OrbitClient {\npublic:\n    OrbitClient();\n    \
+             void RemoveSavegame(unsigned int, IRemoveSavegameListener *, unsigned int, \
+             unsigned int);\n};\n"
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+use std::io::{Seek, Write};
+
+use anyhow::Result;
+use ar_archive_writer::{write_import_library, COFFShortExport, MachineTypes};
+
+use crate::exports::Export;
+
+/// Renders a module-definition file, for toolchains that build their own
+/// import library (`llvm-dlltool -d`, `lib /def:`).
+pub fn generate_def(dll_name: &str, exports: &[Export]) -> String {
+    let mut def = format!("LIBRARY {}\nEXPORTS\n", dll_name);
+
+    for export in exports {
+        def.push_str(&format!("    {}\n", export.symbol));
+    }
+
+    def
+}
+
+/// Writes an i386 MSVC import library for the given exports.
+pub fn write_implib<W: Write + Seek>(
+    writer: &mut W,
+    dll_name: &str,
+    exports: &[Export],
+) -> Result<()> {
+    let exports = exports
+        .iter()
+        .map(|export| COFFShortExport {
+            name: export.symbol.clone(),
+            ext_name: None,
+            symbol_name: None,
+            alias_target: None,
+            ordinal: 0,
+            noname: false,
+            data: false,
+            private: false,
+            constant: false,
+        })
+        .collect::<Vec<_>>();
+
+    write_import_library(writer, dll_name, &exports, MachineTypes::I386, false, false)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn exports() -> Vec<Export> {
+        vec![Export {
+            symbol: "?Update@OrbitClient@orbitclient@mg@@QAEXXZ".to_owned(),
+            function: "orbit_client_update".to_owned(),
+            args: 0,
+        }]
+    }
+
+    #[test]
+    fn generates_def() {
+        assert_eq!(
+            generate_def("ubiorbitapi_r2.dll", &exports()),
+            "LIBRARY ubiorbitapi_r2.dll\nEXPORTS\n    ?Update@OrbitClient@orbitclient@mg@@QAEXXZ\n"
+        );
+    }
+
+    #[test]
+    fn writes_implib() {
+        let mut implib = Cursor::new(Vec::new());
+
+        write_implib(&mut implib, "ubiorbitapi_r2.dll", &exports()).unwrap();
+
+        let implib = implib.into_inner();
+        let symbol = b"?Update@OrbitClient@orbitclient@mg@@QAEXXZ";
+
+        assert!(implib.starts_with(b"!<arch>\n"));
+        assert!(implib.windows(symbol.len()).any(|window| window == symbol));
+    }
+}
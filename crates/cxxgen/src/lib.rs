@@ -0,0 +1,5 @@
+pub mod consts;
+pub mod exports;
+pub mod header;
+pub mod implib;
+pub mod listeners;
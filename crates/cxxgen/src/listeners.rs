@@ -0,0 +1,177 @@
+use anyhow::{anyhow, Result};
+use syn::{GenericArgument, Item, PathArguments, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Listener {
+    pub name: String,
+    pub args: Vec<(String, String)>,
+}
+
+/// Collects the `I*Listener` structs of a source file, with the C++ types of
+/// the arguments their `callback` vtable entry takes.
+pub fn read_listeners(source: &str) -> Result<Vec<Listener>> {
+    let file = syn::parse_file(source)?;
+    let mut listeners = Vec::new();
+
+    for item in file.items {
+        let item = match item {
+            Item::Struct(item) => item,
+            _ => continue,
+        };
+
+        let name = item.ident.to_string();
+
+        if !name.starts_with('I') || !name.ends_with("Listener") {
+            continue;
+        }
+
+        let callback = item
+            .fields
+            .iter()
+            .find(|field| {
+                field
+                    .ident
+                    .as_ref()
+                    .is_some_and(|ident| ident == "callback")
+            })
+            .ok_or_else(|| anyhow!("{} has no callback!", name))?;
+
+        let func = match &callback.ty {
+            Type::Ptr(ptr) => match &*ptr.elem {
+                Type::BareFn(func) => func,
+                _ => return Err(anyhow!("{} callback is not a function pointer!", name)),
+            },
+            _ => return Err(anyhow!("{} callback is not a pointer!", name)),
+        };
+
+        let mut args = Vec::new();
+
+        for input in &func.inputs {
+            let arg_name = input
+                .name
+                .as_ref()
+                .map(|(ident, _)| ident.to_string())
+                .unwrap_or_default();
+
+            args.push((arg_name, cxx_type(&input.ty)?));
+        }
+
+        listeners.push(Listener { name, args });
+    }
+
+    Ok(listeners)
+}
+
+fn cxx_type(ty: &Type) -> Result<String> {
+    match ty {
+        Type::Ptr(ptr) => {
+            let elem = cxx_type(&ptr.elem)?;
+            let is_primitive = !matches!(&*ptr.elem, Type::Path(path) if is_class(path));
+
+            Ok(
+                match (
+                    ptr.const_token.is_some() && is_primitive,
+                    elem.ends_with('*'),
+                ) {
+                    (true, _) => format!("{} const *", elem),
+                    (false, true) => format!("{}*", elem),
+                    (false, false) => format!("{} *", elem),
+                },
+            )
+        }
+        Type::Path(path) => {
+            let segment = path
+                .path
+                .segments
+                .last()
+                .ok_or_else(|| anyhow!("Empty type path!"))?;
+
+            let name = match segment.ident.to_string().as_str() {
+                "u8" => "unsigned char".to_owned(),
+                "u16" => "unsigned short".to_owned(),
+                "u32" => "unsigned int".to_owned(),
+                "i32" => "int".to_owned(),
+                "bool" => "bool".to_owned(),
+                "c_char" | "i8" => "char".to_owned(),
+                "c_void" => "void".to_owned(),
+                "Box" => match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(GenericArgument::Type(inner)) => format!("{} *", cxx_type(inner)?),
+                        _ => return Err(anyhow!("Unsupported Box argument!")),
+                    },
+                    _ => return Err(anyhow!("Unsupported Box argument!")),
+                },
+                class => class.to_owned(),
+            };
+
+            Ok(name)
+        }
+        _ => Err(anyhow!("Unsupported callback argument type!")),
+    }
+}
+
+fn is_class(path: &syn::TypePath) -> bool {
+    path.path.segments.last().is_some_and(|segment| {
+        let name = segment.ident.to_string();
+        name == "Box" || name.starts_with(char::is_uppercase)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_listener_callbacks() {
+        let source = r#"
+            pub struct IGetSavegameListListener {
+                pub callback: *const extern "stdcall" fn(
+                    request_id: u32,
+                    savegame_info_list: *const Box<SavegameInfo>,
+                    list_size: u32,
+                ),
+            }
+
+            pub struct IGetLoginDetailsListener {
+                pub callback: *const extern "stdcall" fn(request_id: u32, username: *const c_char),
+            }
+
+            pub struct NotAListener {
+                pub id: u32,
+            }
+        "#;
+
+        let listeners = read_listeners(source).unwrap();
+
+        assert_eq!(
+            listeners,
+            vec![
+                Listener {
+                    name: "IGetSavegameListListener".to_owned(),
+                    args: vec![
+                        ("request_id".to_owned(), "unsigned int".to_owned()),
+                        (
+                            "savegame_info_list".to_owned(),
+                            "SavegameInfo **".to_owned()
+                        ),
+                        ("list_size".to_owned(), "unsigned int".to_owned()),
+                    ],
+                },
+                Listener {
+                    name: "IGetLoginDetailsListener".to_owned(),
+                    args: vec![
+                        ("request_id".to_owned(), "unsigned int".to_owned()),
+                        ("username".to_owned(), "char const *".to_owned()),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn listener_without_callback_is_an_error() {
+        let source = "pub struct IBrokenListener { pub id: u32 }";
+
+        assert!(read_listeners(source).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::Path;
+
+use cxxgen::exports::read_exports;
+use cxxgen::header::generate_header;
+use cxxgen::listeners::read_listeners;
+
+fn read_source(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../../src")
+        .join(name);
+
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn loader_sources_generate_a_header() {
+    let exports = read_exports(&read_source("api.rs")).unwrap();
+    let listeners = read_listeners(&read_source("types.rs")).unwrap();
+
+    let header = generate_header(&exports, &listeners).unwrap();
+
+    for class in &[
+        "OrbitClient",
+        "SavegameInfo",
+        "SavegameReader",
+        "SavegameWriter",
+    ] {
+        assert!(header.contains(&format!("class __declspec(dllimport) {} {{", class)));
+    }
+
+    for listener in &listeners {
+        assert!(header.contains(&format!("class {} {{", listener.name)));
+    }
+}
@@ -0,0 +1,82 @@
+//! Compares the cached manifest of `FsStorage` with a new `FsStorage` that
+//! reads and writes `Manifest.toml` on every call. Run with `cargo bench --bench manifest`.
+
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use orbitcore::helpers::manifest::write_manifest;
+use orbitcore::helpers::save::get_save_path;
+use orbitcore::models::manifest::{Manifest, Save};
+use orbitcore::storage::{FsStorage, SaveStorage};
+use tempfile::tempdir;
+
+const SAVES: u32 = 500;
+const ROUNDS: u32 = 20;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    f();
+
+    let start = Instant::now();
+
+    for _ in 0..ROUNDS {
+        f();
+    }
+
+    println!("{:<36} {:>12.2?}", name, start.elapsed() / ROUNDS);
+}
+
+fn create_saves(saves_path: &Path) {
+    let mut manifest = Manifest::default();
+
+    fs::create_dir_all(saves_path).unwrap();
+
+    for id in 0..SAVES {
+        fs::write(get_save_path(saves_path, id), id.to_le_bytes()).unwrap();
+        manifest.saves.push(Save {
+            id,
+            name: format!("Save {}", id),
+            locked: false,
+        });
+    }
+
+    write_manifest(saves_path, &manifest).unwrap();
+}
+
+fn main() {
+    let dir = tempdir().unwrap();
+    let saves_path = dir.path();
+    create_saves(saves_path);
+
+    let storage = FsStorage::new(saves_path.to_owned());
+
+    println!("{} saves, mean of {} rounds", SAVES, ROUNDS);
+
+    bench("list, reading the manifest", || {
+        let storage = FsStorage::new(saves_path.to_owned());
+
+        assert_eq!(storage.list(None).unwrap().len(), SAVES as usize);
+    });
+    bench("list, cached", || {
+        assert_eq!(storage.list(None).unwrap().len(), SAVES as usize);
+    });
+    bench("rename all, writing each name", || {
+        for id in 0..SAVES {
+            let storage = FsStorage::new(saves_path.to_owned());
+
+            storage
+                .set_name(None, id, &format!("Renamed {}", id))
+                .unwrap();
+            storage.flush().unwrap();
+        }
+    });
+    bench("rename all, cached then flushed", || {
+        for id in 0..SAVES {
+            storage
+                .set_name(None, id, &format!("Renamed {}", id))
+                .unwrap();
+        }
+
+        storage.flush().unwrap();
+    });
+}
@@ -0,0 +1,65 @@
+//! Compares reading a save into the game's buffer through a temporary `Vec`,
+//! as the loader did before, with reading into the buffer directly and with
+//! a memory-mapped save. Run with `cargo bench --bench read`.
+
+use std::fs::{self, File};
+use std::path::Path;
+use std::time::Instant;
+
+use orbitcore::helpers::save::{get_save_path, read_save_at};
+use orbitcore::storage::{FsStorage, SaveStorage};
+use tempfile::tempdir;
+
+const SAVE_BYTES: usize = 32 * 1024 * 1024;
+const ROUNDS: u32 = 20;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    f();
+
+    let start = Instant::now();
+
+    for _ in 0..ROUNDS {
+        f();
+    }
+
+    println!("{:<36} {:>12.2?}", name, start.elapsed() / ROUNDS);
+}
+
+/// Reads into a new `Vec` of the requested size, then copies it over.
+fn read_through_vec(path: &Path, buffer: &mut [u8]) -> usize {
+    let mut file = File::open(path).unwrap();
+    let mut data = vec![0u8; buffer.len()];
+    let size = read_save_at(&mut file, 0, &mut data).unwrap();
+    buffer[..size].copy_from_slice(&data[..size]);
+
+    size
+}
+
+fn main() {
+    let dir = tempdir().unwrap();
+    let path = get_save_path(dir.path(), 1);
+    let data = (0..SAVE_BYTES).map(|i| i as u8).collect::<Vec<_>>();
+    fs::write(&path, &data).unwrap();
+
+    let storage = FsStorage::new(dir.path().to_owned());
+    let mut mapped = FsStorage::new(dir.path().to_owned());
+    mapped.mmap_threshold = Some(0);
+
+    let mut buffer = vec![0u8; SAVE_BYTES];
+
+    println!("{} MiB save, mean of {} rounds", SAVE_BYTES >> 20, ROUNDS);
+
+    bench("read into a Vec, then copied", || {
+        assert_eq!(read_through_vec(&path, &mut buffer), SAVE_BYTES);
+    });
+    bench("read_at into the buffer", || {
+        let mut save = storage.open_read(None, 1).unwrap();
+        assert_eq!(save.read_at(0, &mut buffer).unwrap(), SAVE_BYTES);
+    });
+    bench("read_at from a mapped save", || {
+        let mut save = mapped.open_read(None, 1).unwrap();
+        assert_eq!(save.read_at(0, &mut buffer).unwrap(), SAVE_BYTES);
+    });
+
+    assert_eq!(buffer, data);
+}
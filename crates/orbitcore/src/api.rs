@@ -0,0 +1,239 @@
+#![allow(unused_variables)]
+
+use std::path::Path;
+
+use anyhow::Result;
+use log::{error, info};
+use widestring::U16CStr;
+
+use crate::error::ErrorCode;
+use crate::helpers::process::start_process;
+use crate::listeners::{
+    GetCdKeysListener, GetSavegameListListener, GetSavegameReaderListener,
+    GetSavegameWriterListener, RemoveSavegameListener, SavegameReadListener, SavegameWriteListener,
+};
+use crate::models::config::Launcher;
+use crate::types::{OrbitClient, SavegameReader, SavegameWriter};
+
+pub fn orbit_client_start_process(
+    launcher: &Launcher,
+    path: Option<&str>,
+    arguments: Option<&str>,
+    working_directory: Option<&str>,
+) {
+    info!(
+        "StartProcess: path: {:?}, arguments: {:?}, working directory: {:?}",
+        path, arguments, working_directory
+    );
+
+    let path = match path {
+        Some(path) if launcher.start_process => Path::new(path),
+        _ => return,
+    };
+
+    let result = start_process(path, arguments, working_directory.map(Path::new));
+
+    if let Err(err) = result {
+        error!("{}", err);
+    }
+}
+
+pub fn orbit_client_start_launcher(
+    client: &mut OrbitClient,
+    launcher: &Launcher,
+    product_id: u32,
+    flags: u32,
+    language: Option<&str>,
+    arguments: Option<&str>,
+) -> bool {
+    info!(
+        "StartLauncher: product id: {}, flags: {}, language: {:?}, arguments: {:?}",
+        product_id, flags, language, arguments
+    );
+
+    if let Err(err) = client.start_launcher(&launcher.command) {
+        error!("{}", err);
+    }
+
+    launcher.result
+}
+
+/// The list stays alive on the client until the next list request, as games
+/// may keep it past the callback.
+pub fn orbit_client_get_savegame_list(
+    client: &mut OrbitClient,
+    request_id: u32,
+    listener: &dyn GetSavegameListListener,
+    product_id: u32,
+) {
+    if let Err(err) = client.pull_savegames(product_id) {
+        error!("{}", err);
+    }
+
+    let result = client.get_savegame_list(product_id);
+
+    client.savegame_info_list = match result {
+        Ok(list) => list,
+        Err(err) => {
+            error!("{}", err);
+            Vec::new()
+        }
+    };
+
+    let sizes = client
+        .savegame_info_list
+        .iter()
+        .map(|info| info.size as u64);
+    info!("Savegames of {}: {}", product_id, client.quota.usage(sizes));
+
+    listener.callback(request_id, &client.savegame_info_list);
+}
+
+pub fn orbit_client_get_savegame_writer(
+    client: &mut OrbitClient,
+    request_id: u32,
+    listener: &dyn GetSavegameWriterListener,
+    product_id: u32,
+    save_game_id: u32,
+    open: bool,
+) {
+    let result = client.get_savegame_writer(request_id, product_id, save_game_id, open);
+
+    match result {
+        Ok(writer) => listener.callback(request_id, ErrorCode::Ok as u32, Some(writer)),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, ErrorCode::from(&err) as u32, None);
+        }
+    }
+}
+
+pub fn orbit_client_get_savegame_reader(
+    client: &mut OrbitClient,
+    request_id: u32,
+    listener: &dyn GetSavegameReaderListener,
+    product_id: u32,
+    save_game_id: u32,
+) {
+    let result = client.get_savegame_reader(request_id, product_id, save_game_id);
+
+    match result {
+        Ok(reader) => listener.callback(request_id, ErrorCode::Ok as u32, Some(reader)),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, ErrorCode::from(&err) as u32, None);
+        }
+    }
+}
+
+pub fn orbit_client_remove_savegame(
+    client: &OrbitClient,
+    request_id: u32,
+    listener: &dyn RemoveSavegameListener,
+    product_id: u32,
+    save_game_id: u32,
+) {
+    let result = (|| -> Result<()> {
+        client.remove_savegame(product_id, save_game_id)?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(_) => listener.callback(request_id, true),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, false);
+        }
+    }
+}
+
+pub fn orbit_client_get_cd_keys(
+    client: &OrbitClient,
+    request_id: u32,
+    listener: &dyn GetCdKeysListener,
+    product_id: u32,
+) {
+    match client.get_cd_keys(product_id) {
+        Ok(cd_keys) => listener.callback(request_id, cd_keys),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, &[]);
+        }
+    }
+}
+
+pub fn orbit_client_update(client: &mut OrbitClient) {
+    client.release_closed_handles();
+
+    if let Err(err) = client.flush_savegames() {
+        error!("{}", err);
+    }
+}
+
+pub fn orbit_client_get_request_unique_id(client: &mut OrbitClient) -> u32 {
+    client.get_next_request_id()
+}
+
+pub fn savegame_reader_read(
+    reader: &SavegameReader,
+    request_id: u32,
+    listener: &dyn SavegameReadListener,
+    offset: u32,
+    buffer: &mut [u8],
+) {
+    let result = reader.read(offset as u64, buffer);
+
+    match result {
+        Ok(size) => listener.callback(request_id, size as u32),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, 0);
+        }
+    }
+}
+
+pub fn savegame_reader_close(reader: &SavegameReader) {
+    reader.close();
+}
+
+pub fn savegame_writer_write(
+    writer: &SavegameWriter,
+    request_id: u32,
+    listener: &dyn SavegameWriteListener,
+    buffer: &[u8],
+) {
+    let result = (|| -> Result<()> {
+        writer.write(buffer)?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(_) => listener.callback(request_id, buffer.len() as u32),
+        Err(err) => {
+            error!("{}", err);
+            listener.callback(request_id, 0);
+        }
+    }
+}
+
+pub fn savegame_writer_set_name(writer: &SavegameWriter, name: &U16CStr) -> bool {
+    let result = (|| -> Result<()> {
+        let u16name = name.to_string()?;
+
+        writer.set_name(u16name)?;
+        Ok(())
+    })();
+
+    match result {
+        Ok(_) => return true,
+        Err(err) => error!("{}", err),
+    }
+
+    false
+}
+
+pub fn savegame_writer_close(writer: &SavegameWriter) {
+    if let Err(err) = writer.close() {
+        error!("{}", err);
+    }
+}
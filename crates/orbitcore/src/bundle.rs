@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Read, Write};
+
+use anyhow::{anyhow, Error, Result};
+use sha2::{Digest, Sha256};
+use tar::{Archive, Builder, Header};
+
+use crate::consts::{BUNDLE_MANIFEST_NAME, SAVE_FILE_EXTENSION};
+use crate::helpers::time::get_timestamp;
+use crate::models::bundle::{Bundle, BundleSave};
+use crate::models::config::Orbit;
+use crate::models::manifest::Save;
+use crate::storage::{read_save_data, write_save_data, SaveStorage};
+
+/// What to do with a bundled save whose id is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+    /// Stores it under the next free id.
+    Remap,
+    Overwrite,
+}
+
+#[inline]
+fn get_sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[inline]
+fn get_bundle_save_path(id: u32) -> String {
+    format!("Saves/{}.{}", id, SAVE_FILE_EXTENSION)
+}
+
+#[inline]
+fn invalid_bundle(message: String) -> Error {
+    Error::new(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+fn append<W: Write>(builder: &mut Builder<W>, path: &str, data: &[u8]) -> Result<()> {
+    let mut header = Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(get_timestamp());
+
+    builder.append_data(&mut header, path, data)?;
+
+    Ok(())
+}
+
+/// Writes a tar with a `Bundle.toml` and the saves of `product` with the given
+/// ids, or all of them if there are none.
+pub fn export_bundle(
+    storage: &dyn SaveStorage,
+    orbit: &Orbit,
+    product: Option<u32>,
+    ids: &[u32],
+    writer: impl Write,
+) -> Result<Bundle> {
+    let mut saves = storage.list(product)?;
+
+    if !ids.is_empty() {
+        if let Some(id) = ids
+            .iter()
+            .find(|id| !saves.iter().any(|save| save.id == **id))
+        {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Savegame {} not found", id),
+            )));
+        }
+
+        saves.retain(|save| ids.contains(&save.id));
+    }
+
+    let mut bundle = Bundle {
+        name: orbit.name.clone(),
+        product_id: product.unwrap_or(orbit.product_id),
+        saves: Vec::new(),
+    };
+    let mut files = Vec::new();
+
+    for save in saves {
+        let data = read_save_data(storage, product, &save)?;
+
+        bundle.saves.push(BundleSave {
+            save: Save {
+                id: save.id,
+                locked: storage.is_locked(product, save.id)?,
+                name: save.name,
+            },
+            size: data.len() as u64,
+            sha256: get_sha256(&data),
+        });
+        files.push((get_bundle_save_path(save.id), data));
+    }
+
+    let mut builder = Builder::new(writer);
+    append(
+        &mut builder,
+        BUNDLE_MANIFEST_NAME,
+        toml::to_string(&bundle)?.as_bytes(),
+    )?;
+
+    for (path, data) in &files {
+        append(&mut builder, path, data)?;
+    }
+
+    builder.into_inner()?.flush()?;
+
+    Ok(bundle)
+}
+
+/// Reads a bundle made for `product_id` into the saves of `product`. Every
+/// save is checked before any is stored, and a locked save is never
+/// overwritten. Returns the bundled ids with the ids they were stored under.
+pub fn import_bundle(
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+    product_id: u32,
+    reader: impl Read,
+    collision: Collision,
+) -> Result<Vec<(u32, u32)>> {
+    let mut bundle = None;
+    let mut files = BTreeMap::new();
+
+    for entry in Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+
+        if path == BUNDLE_MANIFEST_NAME {
+            bundle = Some(toml::from_str::<Bundle>(std::str::from_utf8(&data)?)?);
+        } else {
+            files.insert(path, data);
+        }
+    }
+
+    let bundle = bundle
+        .ok_or_else(|| invalid_bundle(format!("The bundle has no {}", BUNDLE_MANIFEST_NAME)))?;
+
+    if bundle.product_id != product_id {
+        return Err(invalid_bundle(format!(
+            "The bundle is for product {} ({}), not {}",
+            bundle.product_id, bundle.name, product_id
+        )));
+    }
+
+    let mut saves = Vec::new();
+
+    for bundle_save in &bundle.saves {
+        let id = bundle_save.save.id;
+        let data = files
+            .remove(&get_bundle_save_path(id))
+            .ok_or_else(|| invalid_bundle(format!("Savegame {} is missing from the bundle", id)))?;
+
+        if data.len() as u64 != bundle_save.size || get_sha256(&data) != bundle_save.sha256 {
+            return Err(invalid_bundle(format!(
+                "Savegame {} does not match its checksum",
+                id
+            )));
+        }
+
+        saves.push((&bundle_save.save, data));
+    }
+
+    let mut used_ids = storage
+        .list(product)?
+        .into_iter()
+        .map(|save| save.id)
+        .collect::<BTreeSet<_>>();
+    let mut ids = Vec::new();
+
+    for (save, _) in &saves {
+        let id = match collision {
+            Collision::Remap if used_ids.contains(&save.id) => {
+                let last_id = used_ids.iter().next_back().copied().unwrap_or_default();
+                last_id
+                    .checked_add(1)
+                    .ok_or_else(|| anyhow!("No savegame id left for {}", save.id))?
+            }
+            _ => save.id,
+        };
+
+        if used_ids.contains(&id) && storage.is_locked(product, id)? {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Savegame {} is locked", id),
+            )));
+        }
+
+        used_ids.insert(id);
+        ids.push((save.id, id));
+    }
+
+    for ((save, data), (_, id)) in saves.iter().zip(&ids) {
+        write_save_data(storage, product, *id, &save.name, data)?;
+    }
+
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::storage::{FsStorage, MemoryStorage};
+
+    fn orbit() -> Orbit {
+        Orbit {
+            name: "TestGame".to_owned(),
+            product_id: 46,
+            ..Default::default()
+        }
+    }
+
+    fn storage(saves: &[(u32, &str, &[u8])]) -> MemoryStorage {
+        let storage = MemoryStorage::default();
+
+        for (id, name, data) in saves {
+            write_save_data(&storage, None, *id, name, data).unwrap();
+        }
+
+        storage
+    }
+
+    fn saves(storage: &MemoryStorage) -> Vec<(u32, String, Vec<u8>)> {
+        storage
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|save| {
+                let data = read_save_data(storage, None, &save).unwrap();
+                (save.id, save.name, data)
+            })
+            .collect()
+    }
+
+    fn export(storage: &MemoryStorage, ids: &[u32]) -> Vec<u8> {
+        let mut bundle = Vec::new();
+        export_bundle(storage, &orbit(), None, ids, &mut bundle).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn round_trip_of_selected_saves() {
+        let from = storage(&[(1, "First", b"one"), (2, "Second", b"two")]);
+        let bundle = export(&from, &[2]);
+        let to = MemoryStorage::default();
+
+        let ids = import_bundle(&to, None, 46, &bundle[..], Collision::Remap).unwrap();
+
+        assert_eq!(ids, vec![(2, 2)]);
+        assert_eq!(saves(&to), vec![(2, "Second".to_owned(), b"two".to_vec())]);
+    }
+
+    #[test]
+    fn export_of_missing_save_is_an_error() {
+        let from = storage(&[(1, "First", b"one")]);
+
+        assert!(export_bundle(&from, &orbit(), None, &[3], Vec::new()).is_err());
+    }
+
+    #[test]
+    fn other_products_are_rejected() {
+        let bundle = export(&storage(&[(1, "First", b"one")]), &[]);
+        let to = MemoryStorage::default();
+
+        assert!(import_bundle(&to, None, 47, &bundle[..], Collision::Remap).is_err());
+        assert!(to.list(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn collisions_are_remapped_or_overwritten() {
+        let bundle = export(
+            &storage(&[(1, "First", b"one"), (2, "Second", b"two")]),
+            &[],
+        );
+
+        let to = storage(&[(1, "Mine", b"mine"), (5, "Other", b"other")]);
+        let ids = import_bundle(&to, None, 46, &bundle[..], Collision::Remap).unwrap();
+
+        assert_eq!(ids, vec![(1, 6), (2, 2)]);
+        assert_eq!(saves(&to)[0], (1, "Mine".to_owned(), b"mine".to_vec()));
+
+        let to = storage(&[(1, "Mine", b"mine")]);
+        let ids = import_bundle(&to, None, 46, &bundle[..], Collision::Overwrite).unwrap();
+
+        assert_eq!(ids, vec![(1, 1), (2, 2)]);
+        assert_eq!(saves(&to)[0], (1, "First".to_owned(), b"one".to_vec()));
+    }
+
+    /// A folder with saves 1 and 2, of which 2 is locked.
+    fn locked_storage(saves_path: &Path) -> FsStorage {
+        let storage = FsStorage::new(saves_path.to_owned());
+        write_save_data(&storage, None, 1, "Mine", b"mine").unwrap();
+        write_save_data(&storage, None, 2, "Locked", b"locked").unwrap();
+        storage.set_locked(None, 2, true).unwrap();
+
+        storage
+    }
+
+    #[test]
+    fn locked_flag_is_exported() {
+        let dir = tempdir().unwrap();
+        let from = locked_storage(dir.path());
+
+        let bundle = export_bundle(&from, &orbit(), None, &[], Vec::new()).unwrap();
+        let locked = bundle
+            .saves
+            .iter()
+            .map(|save| (save.save.id, save.save.locked))
+            .collect::<Vec<_>>();
+
+        assert_eq!(locked, vec![(1, false), (2, true)]);
+    }
+
+    #[test]
+    fn locked_saves_are_not_overwritten() {
+        let bundle = export(
+            &storage(&[(1, "First", b"one"), (2, "Second", b"two")]),
+            &[],
+        );
+        let dir = tempdir().unwrap();
+        let to = locked_storage(dir.path());
+
+        assert!(import_bundle(&to, None, 46, &bundle[..], Collision::Overwrite).is_err());
+        assert_eq!(
+            read_save_data(&to, None, &to.list(None).unwrap()[0]).unwrap(),
+            b"mine"
+        );
+
+        let ids = import_bundle(&to, None, 46, &bundle[..], Collision::Remap).unwrap();
+
+        assert_eq!(ids, vec![(1, 3), (2, 4)]);
+        assert_eq!(
+            read_save_data(&to, None, &to.list(None).unwrap()[1]).unwrap(),
+            b"locked"
+        );
+    }
+
+    #[test]
+    fn corrupted_saves_are_rejected() {
+        let bundle = Bundle {
+            name: "TestGame".to_owned(),
+            product_id: 46,
+            saves: vec![BundleSave {
+                save: Save {
+                    id: 1,
+                    name: "First".to_owned(),
+                    locked: false,
+                },
+                size: 3,
+                sha256: get_sha256(b"one"),
+            }],
+        };
+
+        let mut builder = Builder::new(Vec::new());
+        append(
+            &mut builder,
+            BUNDLE_MANIFEST_NAME,
+            toml::to_string(&bundle).unwrap().as_bytes(),
+        )
+        .unwrap();
+        append(&mut builder, &get_bundle_save_path(1), b"two").unwrap();
+        let data = builder.into_inner().unwrap();
+
+        let to = MemoryStorage::default();
+
+        assert!(import_bundle(&to, None, 46, &data[..], Collision::Overwrite).is_err());
+        assert!(to.list(None).unwrap().is_empty());
+    }
+}
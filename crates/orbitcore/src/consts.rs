@@ -0,0 +1,17 @@
+pub const CONFIG_NAME: &str = "Orbit.toml";
+pub const SAVES_MANIFEST_NAME: &str = "Manifest.toml";
+pub const SAVES_LOCK_NAME: &str = "Saves.lock";
+pub const SAVES_LOCK_RETRIES: u32 = 50;
+pub const SAVES_LOCK_RETRY_MS: u64 = 20;
+pub const SAVE_FILE_EXTENSION: &str = "save";
+pub const SAVES_DATABASE_NAME: &str = "Saves.db";
+pub const INSTALLATION_PROBE_NAME: &str = ".orbit_write_probe";
+pub const SANDBOX_ENV_NAME: &str = "ORBIT_SANDBOX";
+pub const SYNC_STATE_NAME: &str = "Sync.toml";
+pub const SYNC_INDEX_NAME: &str = "Index.toml";
+pub const SYNC_TIMEOUT_SECS: u64 = 10;
+pub const SYNC_RETRIES: u32 = 3;
+pub const BUNDLE_MANIFEST_NAME: &str = "Bundle.toml";
+pub const OFFICIAL_HEADER_SIZE: usize = 552;
+pub const TRASH_FOLDER_NAME: &str = "Trash";
+pub const TRASH_INDEX_NAME: &str = "Trash.toml";
@@ -0,0 +1,86 @@
+use std::io;
+
+use anyhow::Error;
+
+/// Error codes reported through the `unk`/error slot of Orbit listeners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum ErrorCode {
+    Ok = 0,
+    Unknown = 1,
+    NotFound = 2,
+    AccessDenied = 3,
+    OutOfSpace = 4,
+    InvalidData = 5,
+    InUse = 6,
+    InvalidArgument = 7,
+}
+
+impl From<&Error> for ErrorCode {
+    fn from(err: &Error) -> Self {
+        match err.downcast_ref::<io::Error>().map(io::Error::kind) {
+            Some(io::ErrorKind::NotFound) => ErrorCode::NotFound,
+            Some(io::ErrorKind::PermissionDenied) => ErrorCode::AccessDenied,
+            Some(io::ErrorKind::StorageFull) => ErrorCode::OutOfSpace,
+            Some(io::ErrorKind::ResourceBusy) => ErrorCode::InUse,
+            Some(io::ErrorKind::InvalidInput) => ErrorCode::InvalidArgument,
+            Some(io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof) => {
+                ErrorCode::InvalidData
+            }
+            Some(_) => ErrorCode::Unknown,
+            None if err.is::<toml::de::Error>() => ErrorCode::InvalidData,
+            None => ErrorCode::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn io_error(kind: io::ErrorKind) -> Error {
+        Error::new(io::Error::from(kind))
+    }
+
+    #[test]
+    fn maps_io_errors() {
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::NotFound)),
+            ErrorCode::NotFound
+        );
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::PermissionDenied)),
+            ErrorCode::AccessDenied
+        );
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::StorageFull)),
+            ErrorCode::OutOfSpace
+        );
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::ResourceBusy)),
+            ErrorCode::InUse
+        );
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::InvalidInput)),
+            ErrorCode::InvalidArgument
+        );
+        assert_eq!(
+            ErrorCode::from(&io_error(io::ErrorKind::Interrupted)),
+            ErrorCode::Unknown
+        );
+    }
+
+    #[test]
+    fn maps_manifest_errors() {
+        let err = Error::new(toml::from_str::<toml::Value>("=").unwrap_err());
+
+        assert_eq!(ErrorCode::from(&err), ErrorCode::InvalidData);
+    }
+
+    #[test]
+    fn maps_other_errors() {
+        assert_eq!(ErrorCode::from(&anyhow!("Unknown")), ErrorCode::Unknown);
+    }
+}
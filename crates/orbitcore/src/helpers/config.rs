@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use ustr::Ustr;
+
+use crate::models::config::{Config, Orbit, Storage};
+
+#[inline]
+pub fn read_config(path: &Path) -> Result<Config> {
+    let config_str = fs::read_to_string(path)?;
+    let config = toml::from_str(&config_str)?;
+
+    Ok(config)
+}
+
+/// Interns the configured CD keys per product id, skipping empty keys.
+#[inline]
+pub fn get_cd_keys(orbit: &Orbit) -> HashMap<u32, Vec<Ustr>> {
+    let intern = |cd_keys: &[String]| {
+        cd_keys
+            .iter()
+            .filter(|cd_key| !cd_key.is_empty())
+            .map(|cd_key| Ustr::from(cd_key))
+            .collect::<Vec<_>>()
+    };
+
+    let mut cd_keys = HashMap::new();
+    cd_keys.insert(orbit.product_id, intern(&orbit.cd_keys));
+
+    for product in &orbit.product_cd_keys {
+        cd_keys
+            .entry(product.product_id)
+            .or_insert_with(Vec::new)
+            .extend(intern(&product.cd_keys));
+    }
+
+    cd_keys
+}
+
+/// Whether changes to the saves are thrown away, by `Sandbox` in the config or
+/// by `env`, the value of the `ORBIT_SANDBOX` environment variable, being
+/// anything but `0`.
+#[inline]
+pub fn is_sandbox(storage: &Storage, env: Option<&OsStr>) -> bool {
+    storage.sandbox || env.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::consts::CONFIG_NAME;
+    use crate::models::config::{Backend, Conflict, Protocol};
+
+    const CONFIG: &str = r#"
+[Orbit]
+Name = "TestGame"
+Saves = "<default>"
+ProductId = 42
+CdKeys = ["AAAA-BBBB"]
+
+[Orbit.Log]
+Write = false
+Path = "Orbit.log"
+
+[Orbit.Profile]
+AccountId = "Account"
+Username = "User"
+Password = "Password"
+
+[[Orbit.ProductCdKeys]]
+ProductId = 43
+CdKeys = ["CCCC-DDDD", ""]
+
+[Orbit.Launcher]
+Result = true
+Command = ["Launcher.exe", "--silent"]
+
+[Orbit.Storage]
+Backend = "Memory"
+ReadOnly = true
+MmapThreshold = 1048576
+
+[Orbit.Trash]
+MaxAgeDays = 7
+
+[Orbit.Sync]
+Url = "http://nas.local:8080/saves"
+Protocol = "WebDav"
+Conflict = "NewestWins"
+"#;
+
+    #[test]
+    fn reads_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_NAME);
+        fs::write(&path, CONFIG).unwrap();
+
+        let config = read_config(&path).unwrap();
+
+        assert_eq!(config.orbit.name, "TestGame");
+        assert_eq!(config.orbit.product_id, 42);
+        assert_eq!(config.orbit.saves, "<default>");
+        assert_eq!(config.orbit.cd_keys, vec!["AAAA-BBBB"]);
+        assert!(!config.orbit.log.write);
+        assert_eq!(config.orbit.profile.username, "User");
+        assert!(config.orbit.launcher.result);
+        assert_eq!(
+            config.orbit.launcher.command,
+            vec!["Launcher.exe", "--silent"]
+        );
+        assert!(!config.orbit.launcher.start_process);
+        assert_eq!(config.orbit.storage.backend, Backend::Memory);
+        assert!(config.orbit.storage.read_only);
+        assert_eq!(config.orbit.storage.mmap_threshold, Some(1048576));
+        assert!(config.orbit.trash.enabled);
+        assert_eq!(config.orbit.trash.max_age_days, 7);
+
+        let sync = config.orbit.sync.unwrap();
+
+        assert_eq!(sync.url, "http://nas.local:8080/saves");
+        assert_eq!(sync.protocol, Protocol::WebDav);
+        assert_eq!(sync.conflict, Conflict::NewestWins);
+        assert_eq!(sync.authorization, None);
+    }
+
+    #[test]
+    fn launcher_is_optional() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_NAME);
+        let config = CONFIG.split("[Orbit.Launcher]").next().unwrap();
+        fs::write(&path, config).unwrap();
+
+        let config = read_config(&path).unwrap();
+
+        assert!(!config.orbit.launcher.result);
+        assert!(config.orbit.launcher.command.is_empty());
+        assert_eq!(config.orbit.storage.backend, Backend::Filesystem);
+        assert_eq!(config.orbit.trash.max_age_days, 30);
+        assert!(config.orbit.sync.is_none());
+    }
+
+    #[test]
+    fn cd_keys_per_product() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_NAME);
+        fs::write(&path, CONFIG).unwrap();
+
+        let config = read_config(&path).unwrap();
+        let cd_keys = get_cd_keys(&config.orbit);
+
+        assert_eq!(cd_keys[&42], vec![Ustr::from("AAAA-BBBB")]);
+        assert_eq!(cd_keys[&43], vec![Ustr::from("CCCC-DDDD")]);
+        assert!(!cd_keys.contains_key(&44));
+    }
+
+    #[test]
+    fn sandbox_by_config_or_environment() {
+        let mut storage = Storage::default();
+
+        assert!(!is_sandbox(&storage, None));
+        assert!(!is_sandbox(&storage, Some(OsStr::new(""))));
+        assert!(!is_sandbox(&storage, Some(OsStr::new("0"))));
+        assert!(is_sandbox(&storage, Some(OsStr::new("1"))));
+
+        storage.sandbox = true;
+        assert!(is_sandbox(&storage, None));
+    }
+
+    #[test]
+    fn missing_config_is_an_error() {
+        let dir = tempdir().unwrap();
+
+        assert!(read_config(&dir.path().join(CONFIG_NAME)).is_err());
+    }
+
+    #[test]
+    fn invalid_config_is_an_error() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(CONFIG_NAME);
+        fs::write(&path, "[Orbit]\nName = 1").unwrap();
+
+        assert!(read_config(&path).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Error, Result};
+use log::{debug, warn};
+
+use crate::consts::{
+    SAVES_LOCK_NAME, SAVES_LOCK_RETRIES, SAVES_LOCK_RETRY_MS, SAVES_MANIFEST_NAME,
+};
+use crate::models::manifest::Manifest;
+
+/// An advisory lock on a saves folder, held by one process at a time and
+/// released on drop.
+#[derive(Debug)]
+pub struct SavesLock {
+    _file: File,
+}
+
+#[inline]
+pub fn get_manifest_path(saves_path: &Path) -> PathBuf {
+    saves_path.join(SAVES_MANIFEST_NAME)
+}
+
+/// When `Manifest.toml` was last written and its size, to notice edits by
+/// other processes. `None` without a manifest.
+#[inline]
+pub fn get_manifest_stamp(saves_path: &Path) -> Result<Option<(SystemTime, u64)>> {
+    let path = get_manifest_path(saves_path);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let metadata = fs::metadata(path)?;
+
+    Ok(Some((metadata.modified()?, metadata.len())))
+}
+
+#[inline]
+pub fn read_manifest(saves_path: &Path) -> Result<Manifest> {
+    let path = get_manifest_path(saves_path);
+
+    let manifest_str = fs::read_to_string(path)?;
+    let manifest = toml::from_str(&manifest_str)?;
+
+    Ok(manifest)
+}
+
+/// Locks the saves folder against other processes, retrying for a while
+/// before giving up with `ResourceBusy`.
+#[inline]
+pub fn lock_saves(saves_path: &Path) -> Result<SavesLock> {
+    if !saves_path.exists() {
+        fs::create_dir_all(saves_path)?;
+    }
+
+    let path = saves_path.join(SAVES_LOCK_NAME);
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)?;
+
+    for retry in 0..=SAVES_LOCK_RETRIES {
+        match file.try_lock() {
+            Ok(()) => return Ok(SavesLock { _file: file }),
+            Err(TryLockError::WouldBlock) if retry == 0 => {
+                warn!("{} is locked by another process, waiting", path.display());
+            }
+            Err(TryLockError::WouldBlock) => debug!("{} is still locked", path.display()),
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+
+        thread::sleep(Duration::from_millis(SAVES_LOCK_RETRY_MS));
+    }
+
+    Err(Error::new(io::Error::new(
+        io::ErrorKind::ResourceBusy,
+        format!("{} stayed locked by another process", path.display()),
+    )))
+}
+
+/// Replaces a file through a temporary file next to it, so it is never left
+/// half written.
+#[inline]
+pub fn replace_file(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+
+    let mut file = File::create(&temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(temp_path, path)?;
+
+    Ok(())
+}
+
+/// Replaces `Manifest.toml` through a temporary file, so it is never left
+/// half written. Changes based on the current manifest should go through
+/// `update_manifest` instead.
+#[inline]
+pub fn write_manifest(saves_path: &Path, manifest: &Manifest) -> Result<()> {
+    let manifest_str = toml::to_string(manifest)?;
+
+    replace_file(&get_manifest_path(saves_path), manifest_str.as_bytes())
+}
+
+/// Reads, changes and writes `Manifest.toml` while holding the lock of the
+/// saves folder. A missing manifest starts out empty.
+#[inline]
+pub fn update_manifest<T>(
+    saves_path: &Path,
+    update: impl FnOnce(&mut Manifest) -> Result<T>,
+) -> Result<T> {
+    let _lock = lock_saves(saves_path)?;
+
+    let mut manifest = if get_manifest_path(saves_path).exists() {
+        read_manifest(saves_path)?
+    } else {
+        Manifest::default()
+    };
+
+    let result = update(&mut manifest)?;
+    write_manifest(saves_path, &manifest)?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::models::manifest::Save;
+    use crate::storage::{FsStorage, SaveStorage};
+
+    fn set_save_name(saves_path: &Path, id: u32, name: String) -> Result<()> {
+        let storage = FsStorage::new(saves_path.to_owned());
+        storage.set_name(None, id, &name)?;
+        storage.flush()
+    }
+
+    #[test]
+    fn write_then_read_manifest() {
+        let dir = tempdir().unwrap();
+        let saves_path = dir.path().join("Saves");
+        let manifest = Manifest {
+            saves: vec![Save {
+                id: 1,
+                name: "First".to_owned(),
+                locked: false,
+            }],
+        };
+
+        write_manifest(&saves_path, &manifest).unwrap();
+        let read = read_manifest(&saves_path).unwrap();
+
+        assert!(get_manifest_path(&saves_path).is_file());
+        assert_eq!(read.saves.len(), 1);
+        assert_eq!(read.saves[0].id, 1);
+        assert_eq!(read.saves[0].name, "First");
+    }
+
+    #[test]
+    fn missing_manifest_is_an_error() {
+        let dir = tempdir().unwrap();
+
+        assert!(read_manifest(dir.path()).is_err());
+    }
+
+    #[test]
+    fn set_save_name_adds_and_renames() {
+        let dir = tempdir().unwrap();
+
+        set_save_name(dir.path(), 3, "Old".to_owned()).unwrap();
+        set_save_name(dir.path(), 4, "Other".to_owned()).unwrap();
+        set_save_name(dir.path(), 3, "New".to_owned()).unwrap();
+
+        let manifest = read_manifest(dir.path()).unwrap();
+        let names = manifest
+            .saves
+            .iter()
+            .map(|save| (save.id, save.name.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![(3, "New"), (4, "Other")]);
+    }
+
+    #[test]
+    fn writes_leave_only_the_manifest_and_lock() {
+        let dir = tempdir().unwrap();
+
+        set_save_name(dir.path(), 1, "First".to_owned()).unwrap();
+        set_save_name(dir.path(), 1, "Renamed".to_owned()).unwrap();
+
+        let mut files = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+
+        assert_eq!(files, vec![SAVES_MANIFEST_NAME, SAVES_LOCK_NAME]);
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn concurrent_renames_keep_every_entry() {
+        let dir = tempdir().unwrap();
+
+        let threads = (0..4)
+            .map(|thread| {
+                let saves_path = dir.path().to_owned();
+
+                thread::spawn(move || {
+                    for id in 0..5 {
+                        set_save_name(&saves_path, thread * 5 + id, id.to_string()).unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(read_manifest(dir.path()).unwrap().saves.len(), 20);
+    }
+
+    #[test]
+    fn busy_lock_is_reported() {
+        let dir = tempdir().unwrap();
+        let _lock = lock_saves(dir.path()).unwrap();
+
+        let err = set_save_name(dir.path(), 1, "Save".to_owned()).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::ResourceBusy)
+        );
+        assert!(!get_manifest_path(dir.path()).exists());
+    }
+
+    #[test]
+    fn locked_flag_survives_renames() {
+        let dir = tempdir().unwrap();
+
+        fs::write(
+            get_manifest_path(dir.path()),
+            "[[Saves]]\nId = 1\nName = \"QA\"\nLocked = true\n",
+        )
+        .unwrap();
+        set_save_name(dir.path(), 1, "QA reference".to_owned()).unwrap();
+        set_save_name(dir.path(), 2, "Other".to_owned()).unwrap();
+
+        let locked = read_manifest(dir.path())
+            .unwrap()
+            .saves
+            .iter()
+            .map(|save| (save.id, save.locked))
+            .collect::<Vec<_>>();
+
+        assert_eq!(locked, vec![(1, true), (2, false)]);
+
+        let manifest = fs::read_to_string(get_manifest_path(dir.path())).unwrap();
+
+        assert_eq!(manifest.matches("Locked").count(), 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod config;
+pub mod manifest;
+pub mod official;
+pub mod process;
+pub mod save;
+pub mod time;
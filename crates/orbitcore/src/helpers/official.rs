@@ -0,0 +1,360 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use if_chain::if_chain;
+
+use super::manifest::replace_file;
+use super::save::get_save_path;
+use crate::consts::{OFFICIAL_HEADER_SIZE, SAVE_FILE_EXTENSION};
+use crate::storage::{read_save_data, write_save_data, SaveStorage};
+
+/// The name follows the length of the data.
+const NAME_OFFSET: usize = 4;
+/// UTF-16 units of a name, leaving room for its NUL.
+const MAX_NAME_UNITS: usize = (OFFICIAL_HEADER_SIZE - NAME_OFFSET) / 2 - 1;
+
+/// `savegames/<user id>/<product id>` of a Ubisoft Connect install.
+#[inline]
+pub fn get_official_saves_path(savegames_path: &Path, user_id: &str, product_id: u32) -> PathBuf {
+    savegames_path.join(user_id).join(product_id.to_string())
+}
+
+/// The saves folder of `user_id`, or of the only user with saves of the
+/// product.
+#[inline]
+pub fn find_official_saves_path(
+    savegames_path: &Path,
+    product_id: u32,
+    user_id: Option<&str>,
+) -> Result<PathBuf> {
+    if let Some(user_id) = user_id {
+        return Ok(get_official_saves_path(savegames_path, user_id, product_id));
+    }
+
+    let mut paths = Vec::new();
+
+    for entry in fs::read_dir(savegames_path)? {
+        let path = entry?.path().join(product_id.to_string());
+
+        if path.is_dir() {
+            paths.push(path);
+        }
+    }
+
+    match paths.len() {
+        1 => Ok(paths.remove(0)),
+        0 => Err(anyhow!(
+            "No user has saves of {} in {}",
+            product_id,
+            savegames_path.display()
+        )),
+        _ => Err(anyhow!(
+            "Several users have saves of {}, pick one of {:?}",
+            product_id,
+            paths
+        )),
+    }
+}
+
+/// The `<n>.save` files of an official saves folder, by number.
+#[inline]
+pub fn get_official_saves(official_path: &Path) -> Result<Vec<(u32, PathBuf)>> {
+    let mut saves = Vec::new();
+
+    for entry in fs::read_dir(official_path)? {
+        let path = entry?.path();
+
+        if_chain! {
+            if path.is_file();
+            if let Some(ext) = path.extension();
+            if ext == SAVE_FILE_EXTENSION;
+            if let Some(stem) = path.file_stem();
+            if let Ok(id) = stem.to_string_lossy().parse::<u32>();
+
+            then {
+                saves.push((id, path));
+            }
+        }
+    }
+
+    saves.sort_unstable();
+
+    Ok(saves)
+}
+
+#[inline]
+pub fn get_official_save_name(id: u32) -> String {
+    format!("Save {}", id)
+}
+
+/// Splits an official save into the name in its header and the game data.
+/// The header starts with the length of the data and the NUL-terminated
+/// UTF-16 name. Files whose header does not match their length are taken as
+/// game data without a header.
+pub fn parse_official_save(data: &[u8]) -> (Option<String>, &[u8]) {
+    if_chain! {
+        if let Some(header) = data.get(..OFFICIAL_HEADER_SIZE);
+        let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        if length as usize == data.len() - OFFICIAL_HEADER_SIZE;
+
+        then {
+            let units = header[NAME_OFFSET..]
+                .chunks_exact(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .take_while(|&unit| unit != 0);
+            let name = char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect::<String>();
+
+            return (
+                Some(name).filter(|name| !name.is_empty()),
+                &data[OFFICIAL_HEADER_SIZE..],
+            );
+        }
+    }
+
+    (None, data)
+}
+
+/// Puts the header with `name` in front of the game data, cutting names too
+/// long for it.
+pub fn make_official_save(name: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let length = u32::try_from(data.len())
+        .map_err(|_| anyhow!("{} bytes do not fit an official save", data.len()))?;
+
+    let mut units = Vec::new();
+
+    for c in name.chars() {
+        let mut buffer = [0u16; 2];
+        let encoded = c.encode_utf16(&mut buffer);
+
+        if units.len() + encoded.len() > MAX_NAME_UNITS {
+            break;
+        }
+
+        units.extend_from_slice(encoded);
+    }
+
+    let mut save = Vec::with_capacity(OFFICIAL_HEADER_SIZE + data.len());
+    save.extend_from_slice(&length.to_le_bytes());
+
+    for unit in units {
+        save.extend_from_slice(&unit.to_le_bytes());
+    }
+
+    save.resize(OFFICIAL_HEADER_SIZE, 0);
+    save.extend_from_slice(data);
+
+    Ok(save)
+}
+
+/// Copies official saves into `product` of `storage`, named by their headers.
+/// Saves without a name keep the one they already have or are named after
+/// their number.
+pub fn import_official_saves(
+    official_path: &Path,
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+) -> Result<usize> {
+    let saves = get_official_saves(official_path)?;
+
+    for (id, path) in &saves {
+        let data = fs::read(path)?;
+        let (name, data) = parse_official_save(&data);
+
+        let name = match name {
+            Some(name) => name,
+            None => storage
+                .stat(product, *id)?
+                .map_or_else(|| get_official_save_name(*id), |save| save.name),
+        };
+
+        write_save_data(storage, product, *id, &name, data)?;
+    }
+
+    Ok(saves.len())
+}
+
+/// Copies the saves of `product` into an official folder, with their names in
+/// the headers. Each file is replaced through a temporary file, so one that
+/// Ubisoft Connect reads meanwhile is never half written.
+pub fn export_official_saves(
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+    official_path: &Path,
+) -> Result<usize> {
+    let mut files = Vec::new();
+
+    for save in storage.list(product)? {
+        let data = read_save_data(storage, product, &save)?;
+
+        files.push((
+            get_save_path(official_path, save.id),
+            make_official_save(&save.name, &data)?,
+        ));
+    }
+
+    fs::create_dir_all(official_path)?;
+
+    for (path, data) in &files {
+        replace_file(path, data)?;
+    }
+
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::storage::{FsStorage, MemoryStorage};
+
+    fn saves(storage: &dyn SaveStorage, product: Option<u32>) -> Vec<(u32, String, Vec<u8>)> {
+        storage
+            .list(product)
+            .unwrap()
+            .into_iter()
+            .map(|save| {
+                let data = read_save_data(storage, product, &save).unwrap();
+                (save.id, save.name, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_only_user_with_saves() {
+        let dir = tempdir().unwrap();
+        let savegames_path = dir.path();
+
+        fs::create_dir_all(get_official_saves_path(savegames_path, "user-a", 46)).unwrap();
+        fs::create_dir_all(get_official_saves_path(savegames_path, "user-b", 720)).unwrap();
+
+        assert_eq!(
+            find_official_saves_path(savegames_path, 46, None).unwrap(),
+            savegames_path.join("user-a").join("46")
+        );
+        assert!(find_official_saves_path(savegames_path, 1, None).is_err());
+
+        fs::create_dir_all(get_official_saves_path(savegames_path, "user-b", 46)).unwrap();
+
+        assert!(find_official_saves_path(savegames_path, 46, None).is_err());
+        assert_eq!(
+            find_official_saves_path(savegames_path, 46, Some("user-b")).unwrap(),
+            savegames_path.join("user-b").join("46")
+        );
+    }
+
+    #[test]
+    fn headers_carry_names() {
+        let save = make_official_save("Chapter 1 – Île", b"data").unwrap();
+
+        assert_eq!(save.len(), OFFICIAL_HEADER_SIZE + 4);
+        assert_eq!(&save[..4], &[4, 0, 0, 0]);
+        assert_eq!(
+            parse_official_save(&save),
+            (Some("Chapter 1 – Île".to_owned()), &b"data"[..])
+        );
+
+        let long = "x".repeat(1000);
+        let (name, _) = parse_official_save(&make_official_save(&long, b"").unwrap());
+
+        assert_eq!(name.unwrap().len(), MAX_NAME_UNITS);
+    }
+
+    #[test]
+    fn files_without_a_header_are_data() {
+        let mut save = make_official_save("Save", b"data").unwrap();
+        save.push(0);
+
+        assert_eq!(parse_official_save(b"data"), (None, &b"data"[..]));
+        assert_eq!(parse_official_save(&save), (None, &save[..]));
+    }
+
+    #[test]
+    fn import_takes_names_from_headers() {
+        let dir = tempdir().unwrap();
+        let storage = MemoryStorage::default();
+
+        fs::write(
+            dir.path().join("1.save"),
+            make_official_save("Chapter 1", b"one").unwrap(),
+        )
+        .unwrap();
+        fs::write(dir.path().join("2.save"), b"two").unwrap();
+        fs::write(dir.path().join("3.save"), b"three").unwrap();
+        fs::write(dir.path().join("notes.txt"), b"").unwrap();
+        write_save_data(&storage, None, 2, "Chapter 2", b"old").unwrap();
+
+        assert_eq!(
+            import_official_saves(dir.path(), &storage, None).unwrap(),
+            3
+        );
+        assert_eq!(
+            saves(&storage, None),
+            vec![
+                (1, "Chapter 1".to_owned(), b"one".to_vec()),
+                (2, "Chapter 2".to_owned(), b"two".to_vec()),
+                (3, "Save 3".to_owned(), b"three".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_keeps_products_apart() {
+        let dir = tempdir().unwrap();
+        let official_path = dir.path().join("official");
+        let saves_path = dir.path().join("Saves");
+        let storage = Rc::new(FsStorage::new(saves_path.clone()));
+
+        fs::create_dir_all(&official_path).unwrap();
+        fs::write(
+            official_path.join("1.save"),
+            make_official_save("DLC", b"dlc").unwrap(),
+        )
+        .unwrap();
+
+        import_official_saves(&official_path, &*storage, Some(200)).unwrap();
+
+        assert!(storage.list(None).unwrap().is_empty());
+        assert_eq!(
+            fs::read(saves_path.join("200").join("1.save")).unwrap(),
+            b"dlc"
+        );
+    }
+
+    #[test]
+    fn export_writes_names_into_headers() {
+        let dir = tempdir().unwrap();
+        let official_path = dir.path().join("official");
+        let storage = MemoryStorage::default();
+
+        write_save_data(&storage, Some(200), 1, "Chapter 1", b"one").unwrap();
+
+        assert_eq!(
+            export_official_saves(&storage, Some(200), &official_path).unwrap(),
+            1
+        );
+
+        let save = fs::read(official_path.join("1.save")).unwrap();
+        let files = fs::read_dir(&official_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(files, vec!["1.save"]);
+        assert_eq!(
+            parse_official_save(&save),
+            (Some("Chapter 1".to_owned()), &b"one"[..])
+        );
+
+        let imported = MemoryStorage::default();
+        import_official_saves(&official_path, &imported, None).unwrap();
+
+        assert_eq!(saves(&imported, None), saves(&storage, Some(200)));
+    }
+}
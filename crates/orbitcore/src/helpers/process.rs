@@ -0,0 +1,92 @@
+use std::path::Path;
+use std::process::{Child, Command};
+
+use anyhow::Result;
+
+/// Starts `command[0]` with the remaining items as arguments.
+#[inline]
+pub fn start_command(command: &[String]) -> Result<Option<Child>> {
+    let (program, args) = match command.split_first() {
+        Some(command) => command,
+        None => return Ok(None),
+    };
+
+    let child = Command::new(program).args(args).spawn()?;
+
+    Ok(Some(child))
+}
+
+/// Starts `path` with a command line as passed to `StartProcess`.
+#[inline]
+pub fn start_process(
+    path: &Path,
+    arguments: Option<&str>,
+    working_directory: Option<&Path>,
+) -> Result<Child> {
+    let mut command = Command::new(path);
+
+    if let Some(arguments) = arguments {
+        add_arguments(&mut command, arguments);
+    }
+
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
+    let child = command.spawn()?;
+
+    Ok(child)
+}
+
+#[cfg(windows)]
+#[inline]
+fn add_arguments(command: &mut Command, arguments: &str) {
+    use std::os::windows::process::CommandExt;
+
+    command.raw_arg(arguments);
+}
+
+#[cfg(not(windows))]
+#[inline]
+fn add_arguments(command: &mut Command, arguments: &str) {
+    command.args(arguments.split_whitespace());
+}
+
+#[cfg(all(test, unix, not(miri)))]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn empty_command_starts_nothing() {
+        assert!(start_command(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn starts_command() {
+        let command = vec!["sh".to_owned(), "-c".to_owned(), "exit 3".to_owned()];
+
+        let status = start_command(&command).unwrap().unwrap().wait().unwrap();
+
+        assert_eq!(status.code(), Some(3));
+    }
+
+    #[test]
+    fn starts_process_in_working_directory() {
+        let dir = tempdir().unwrap();
+
+        let status = start_process(Path::new("touch"), Some("started"), Some(dir.path()))
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        assert!(status.success());
+        assert!(dir.path().join("started").exists());
+    }
+
+    #[test]
+    fn missing_program_is_an_error() {
+        assert!(start_process(Path::new("/nonexistent/program"), None, None).is_err());
+    }
+}
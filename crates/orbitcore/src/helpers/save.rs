@@ -0,0 +1,222 @@
+use std::io::prelude::*;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use anyhow::{anyhow, Result};
+use fs::{File, OpenOptions};
+use if_chain::if_chain;
+
+use crate::consts::SAVE_FILE_EXTENSION;
+use crate::models::config::Orbit;
+
+#[inline]
+pub fn get_saves_path(orbit: &Orbit) -> Result<PathBuf> {
+    get_game_saves_path(orbit, &env::current_dir()?)
+}
+
+/// The saves path of a game installed in `game_path`.
+#[inline]
+pub fn get_game_saves_path(orbit: &Orbit, game_path: &Path) -> Result<PathBuf> {
+    let path = match orbit.saves.as_str() {
+        "<default>" => game_path.join("Saves"),
+        "<roaming>" => dirs::config_dir()
+            .ok_or_else(|| anyhow!("Unknown config dir!"))?
+            .join("OrbitEmu")
+            .join(&orbit.name)
+            .join("Saves"),
+        _ => game_path.join(&orbit.saves),
+    };
+
+    Ok(path)
+}
+
+#[inline]
+pub fn get_product_saves_path(saves_path: &Path, product_id: u32) -> PathBuf {
+    saves_path.join(product_id.to_string())
+}
+
+/// Products with their own subfolder of saves.
+#[inline]
+pub fn get_product_ids(saves_path: &Path) -> Result<Vec<u32>> {
+    let mut product_ids = Vec::new();
+
+    if !saves_path.exists() {
+        return Ok(product_ids);
+    }
+
+    for entry in fs::read_dir(saves_path)? {
+        let entry = entry?;
+
+        if_chain! {
+            if entry.file_type()?.is_dir();
+            if let Ok(product_id) = entry.file_name().to_string_lossy().parse::<u32>();
+
+            then {
+                product_ids.push(product_id);
+            }
+        }
+    }
+
+    product_ids.sort_unstable();
+
+    Ok(product_ids)
+}
+
+#[inline]
+pub fn get_save_path(saves_path: &Path, id: u32) -> PathBuf {
+    let file = format!("{}.{}", id, SAVE_FILE_EXTENSION);
+    saves_path.join(file)
+}
+
+/// Reads until `buffer` is full or the end of the file is reached. Offsets
+/// past the end read nothing.
+#[inline]
+pub fn read_save_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut read_bytes = 0;
+
+    while read_bytes < buffer.len() {
+        match file.read(&mut buffer[read_bytes..]) {
+            Ok(0) => break,
+            Ok(n) => read_bytes += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Ok(read_bytes)
+}
+
+#[inline]
+pub fn open_save(path: &Path, options: &OpenOptions) -> Result<File> {
+    if let Some(path) = path.parent() {
+        if !path.exists() {
+            fs::create_dir_all(path)?;
+        }
+    }
+
+    let file = options.open(path)?;
+
+    Ok(file)
+}
+
+#[inline]
+pub fn write_save_at(file: &mut File, offset: u64, buffer: &[u8]) -> Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(buffer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn orbit(saves: &str) -> Orbit {
+        Orbit {
+            name: "TestGame".to_owned(),
+            saves: saves.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn create_options() -> OpenOptions {
+        OpenOptions::new().write(true).create(true).to_owned()
+    }
+
+    #[test]
+    fn saves_path_from_config() {
+        let default = get_saves_path(&orbit("<default>")).unwrap();
+        let custom = get_saves_path(&orbit("/tmp/OrbitSaves")).unwrap();
+
+        assert_eq!(default, env::current_dir().unwrap().join("Saves"));
+        assert_eq!(custom, PathBuf::from("/tmp/OrbitSaves"));
+    }
+
+    #[test]
+    fn saves_path_of_another_game() {
+        let game_path = Path::new("/games/TestGame");
+
+        assert_eq!(
+            get_game_saves_path(&orbit("<default>"), game_path).unwrap(),
+            game_path.join("Saves")
+        );
+        assert_eq!(
+            get_game_saves_path(&orbit("MySaves"), game_path).unwrap(),
+            game_path.join("MySaves")
+        );
+        assert_eq!(
+            get_game_saves_path(&orbit("/tmp/OrbitSaves"), game_path).unwrap(),
+            PathBuf::from("/tmp/OrbitSaves")
+        );
+    }
+
+    #[test]
+    fn product_ids_from_subfolders() {
+        let dir = tempdir().unwrap();
+        let saves_path = dir.path();
+
+        fs::create_dir(saves_path.join("200")).unwrap();
+        fs::create_dir(saves_path.join("100")).unwrap();
+        fs::create_dir(saves_path.join("Other")).unwrap();
+        fs::write(get_save_path(saves_path, 1), b"1").unwrap();
+
+        assert_eq!(get_product_ids(saves_path).unwrap(), vec![100, 200]);
+        assert!(get_product_ids(&saves_path.join("Missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn save_path_uses_id_and_extension() {
+        let path = get_save_path(Path::new("Saves"), 7);
+
+        assert_eq!(path, Path::new("Saves").join("7.save"));
+    }
+
+    #[test]
+    fn writes_at_offsets() {
+        let dir = tempdir().unwrap();
+        let path = get_save_path(&dir.path().join("Saves"), 1);
+        let mut file = open_save(&path, &create_options()).unwrap();
+
+        write_save_at(&mut file, 0, b"hello").unwrap();
+        write_save_at(&mut file, 5, b" world").unwrap();
+        write_save_at(&mut file, 0, b"H").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"Hello world");
+    }
+
+    #[test]
+    fn write_then_read_save() {
+        let dir = tempdir().unwrap();
+        let path = get_save_path(dir.path(), 1);
+
+        fs::write(&path, b"hello world").unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        let mut buffer = [0u8; 5];
+
+        assert_eq!(read_save_at(&mut file, 6, &mut buffer).unwrap(), 5);
+        assert_eq!(&buffer, b"world");
+    }
+
+    #[test]
+    fn reads_past_end_of_file() {
+        let dir = tempdir().unwrap();
+        let path = get_save_path(dir.path(), 1);
+        fs::write(&path, b"hello").unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        let mut buffer = [0u8; 4];
+
+        assert_eq!(read_save_at(&mut file, 3, &mut buffer).unwrap(), 2);
+        assert_eq!(&buffer[..2], b"lo");
+        assert_eq!(read_save_at(&mut file, 5, &mut buffer).unwrap(), 0);
+        assert_eq!(read_save_at(&mut file, 1000, &mut buffer).unwrap(), 0);
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use widestring::{U16CStr, U16CString};
+
+use crate::consts::INSTALLATION_PROBE_NAME;
+
+const DEFAULT_LANGUAGE: &str = "en";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum InstallationError {
+    None = 0,
+    ConfigNotLoaded = 1,
+    SavesNotWritable = 2,
+    LogNotOpened = 3,
+    StorageNotOpened = 4,
+}
+
+const ERRORS: &[InstallationError] = &[
+    InstallationError::None,
+    InstallationError::ConfigNotLoaded,
+    InstallationError::SavesNotWritable,
+    InstallationError::LogNotOpened,
+    InstallationError::StorageNotOpened,
+];
+
+const LANGUAGES: &[&str] = &["en", "fr", "de", "es", "it", "ru"];
+
+impl InstallationError {
+    fn message(self, language: &str) -> &'static str {
+        use InstallationError::*;
+
+        match (self, language) {
+            (None, _) => "",
+
+            (ConfigNotLoaded, "fr") => "Impossible de charger Orbit.toml.",
+            (ConfigNotLoaded, "de") => "Orbit.toml konnte nicht geladen werden.",
+            (ConfigNotLoaded, "es") => "No se pudo cargar Orbit.toml.",
+            (ConfigNotLoaded, "it") => "Impossibile caricare Orbit.toml.",
+            (ConfigNotLoaded, "ru") => "Не удалось загрузить Orbit.toml.",
+            (ConfigNotLoaded, _) => "Orbit.toml could not be loaded.",
+
+            (SavesNotWritable, "fr") => {
+                "Le dossier des sauvegardes n'est pas accessible en écriture."
+            }
+            (SavesNotWritable, "de") => "In den Spielstandordner kann nicht geschrieben werden.",
+            (SavesNotWritable, "es") => "No se puede escribir en la carpeta de partidas guardadas.",
+            (SavesNotWritable, "it") => "Impossibile scrivere nella cartella dei salvataggi.",
+            (SavesNotWritable, "ru") => "Нет доступа на запись к папке сохранений.",
+            (SavesNotWritable, _) => "The saves folder is not writable.",
+
+            (LogNotOpened, "fr") => "Impossible d'ouvrir le fichier journal.",
+            (LogNotOpened, "de") => "Die Protokolldatei konnte nicht geöffnet werden.",
+            (LogNotOpened, "es") => "No se pudo abrir el archivo de registro.",
+            (LogNotOpened, "it") => "Impossibile aprire il file di log.",
+            (LogNotOpened, "ru") => "Не удалось открыть файл журнала.",
+            (LogNotOpened, _) => "The log file could not be opened.",
+
+            (StorageNotOpened, "fr") => "Impossible d'ouvrir les sauvegardes.",
+            (StorageNotOpened, "de") => "Die Spielstände konnten nicht geöffnet werden.",
+            (StorageNotOpened, "es") => "No se pudieron abrir las partidas guardadas.",
+            (StorageNotOpened, "it") => "Impossibile aprire i salvataggi.",
+            (StorageNotOpened, "ru") => "Не удалось открыть сохранения.",
+            (StorageNotOpened, _) => "The saves could not be opened.",
+        }
+    }
+}
+
+static MESSAGES: Lazy<HashMap<(InstallationError, &'static str), U16CString>> = Lazy::new(|| {
+    let mut messages = HashMap::new();
+
+    for &error in ERRORS {
+        for &language in LANGUAGES {
+            let message = U16CString::from_str(error.message(language)).unwrap();
+            messages.insert((error, language), message);
+        }
+    }
+
+    messages
+});
+
+/// Result of the startup checks, reported through `GetInstallationErrorNum`
+/// and `GetInstallationErrorString`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Installation {
+    pub errors: Vec<InstallationError>,
+}
+
+impl Installation {
+    /// Probes `saves_path` for writing unless it is `None`, as for sessions
+    /// that never write to the saves folder.
+    pub fn check(
+        config_loaded: bool,
+        storage_opened: bool,
+        saves_path: Option<&Path>,
+        log_opened: bool,
+    ) -> Self {
+        let mut errors = Vec::new();
+
+        if !config_loaded {
+            errors.push(InstallationError::ConfigNotLoaded);
+        }
+
+        if !storage_opened {
+            errors.push(InstallationError::StorageNotOpened);
+        }
+
+        if saves_path.is_some_and(|saves_path| !is_writable(saves_path)) {
+            errors.push(InstallationError::SavesNotWritable);
+        }
+
+        if !log_opened {
+            errors.push(InstallationError::LogNotOpened);
+        }
+
+        Self { errors }
+    }
+
+    pub fn error(&self) -> InstallationError {
+        self.errors
+            .first()
+            .copied()
+            .unwrap_or(InstallationError::None)
+    }
+
+    pub fn error_num(&self) -> u32 {
+        self.error() as u32
+    }
+
+    /// The message for the first error, in the language of a locale such as
+    /// `fr-FR`. The string lives for the whole process.
+    pub fn error_string(&self, locale: Option<&str>) -> &'static U16CStr {
+        let language = locale
+            .and_then(|locale| locale.get(..2))
+            .map(str::to_ascii_lowercase)
+            .and_then(|language| LANGUAGES.iter().find(|&&known| known == language))
+            .copied()
+            .unwrap_or(DEFAULT_LANGUAGE);
+
+        &MESSAGES[&(self.error(), language)]
+    }
+}
+
+pub fn is_writable(path: &Path) -> bool {
+    let probe = path.join(INSTALLATION_PROBE_NAME);
+
+    let writable = fs::create_dir_all(path).is_ok()
+        && OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&probe)
+            .is_ok();
+
+    let _ = fs::remove_file(probe);
+
+    writable
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn healthy_installation() {
+        let dir = tempdir().unwrap();
+        let installation = Installation::check(true, true, Some(&dir.path().join("Saves")), true);
+
+        assert_eq!(installation.error_num(), 0);
+        assert!(installation.error_string(None).is_empty());
+        assert!(dir.path().join("Saves").is_dir());
+        assert!(!dir
+            .path()
+            .join("Saves")
+            .join(INSTALLATION_PROBE_NAME)
+            .exists());
+    }
+
+    #[test]
+    fn reports_first_error() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, b"").unwrap();
+
+        let installation = Installation::check(true, true, Some(&file), false);
+
+        assert_eq!(
+            installation.errors,
+            vec![
+                InstallationError::SavesNotWritable,
+                InstallationError::LogNotOpened
+            ]
+        );
+        assert_eq!(installation.error_num(), 2);
+    }
+
+    #[test]
+    fn saves_are_not_probed_without_a_path() {
+        let installation = Installation::check(false, false, None, true);
+
+        assert_eq!(
+            installation.errors,
+            vec![
+                InstallationError::ConfigNotLoaded,
+                InstallationError::StorageNotOpened
+            ]
+        );
+        assert!(Installation::check(true, true, None, true)
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn storage_errors_come_before_the_saves_folder() {
+        let dir = tempdir().unwrap();
+        let installation = Installation::check(true, false, Some(dir.path()), true);
+
+        assert_eq!(
+            installation.errors,
+            vec![InstallationError::StorageNotOpened]
+        );
+        assert_eq!(installation.error_num(), 4);
+        assert_eq!(
+            installation.error_string(Some("it")).to_string().unwrap(),
+            "Impossibile aprire i salvataggi."
+        );
+    }
+
+    #[test]
+    fn localized_error_strings() {
+        let installation = Installation {
+            errors: vec![InstallationError::ConfigNotLoaded],
+        };
+
+        let english = installation.error_string(None).to_string().unwrap();
+        let french = installation
+            .error_string(Some("fr-FR"))
+            .to_string()
+            .unwrap();
+        let unknown = installation.error_string(Some("xx")).to_string().unwrap();
+
+        assert_eq!(english, "Orbit.toml could not be loaded.");
+        assert_eq!(french, "Impossible de charger Orbit.toml.");
+        assert_eq!(unknown, english);
+    }
+
+    #[test]
+    fn error_strings_are_persistent() {
+        let installation = Installation {
+            errors: vec![InstallationError::LogNotOpened],
+        };
+
+        let first = installation.error_string(Some("de")).as_ptr();
+        let second = installation.error_string(Some("DE-de")).as_ptr();
+
+        assert_eq!(first, second);
+    }
+}
@@ -0,0 +1,11 @@
+pub mod api;
+pub mod bundle;
+pub mod consts;
+pub mod error;
+pub mod helpers;
+pub mod installation;
+pub mod listeners;
+pub mod models;
+pub mod quota;
+pub mod storage;
+pub mod types;
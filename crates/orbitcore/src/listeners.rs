@@ -0,0 +1,31 @@
+use ustr::Ustr;
+
+use crate::types::{SavegameInfo, SavegameReader, SavegameWriter};
+
+pub trait GetSavegameListListener {
+    fn callback(&self, request_id: u32, savegame_info_list: &[Box<SavegameInfo>]);
+}
+
+pub trait GetSavegameWriterListener {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_writer: Option<&SavegameWriter>);
+}
+
+pub trait GetSavegameReaderListener {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_reader: Option<&SavegameReader>);
+}
+
+pub trait RemoveSavegameListener {
+    fn callback(&self, request_id: u32, removed: bool);
+}
+
+pub trait SavegameReadListener {
+    fn callback(&self, request_id: u32, bytes_read: u32);
+}
+
+pub trait SavegameWriteListener {
+    fn callback(&self, request_id: u32, bytes_written: u32);
+}
+
+pub trait GetCdKeysListener {
+    fn callback(&self, request_id: u32, cd_keys: &[Ustr]);
+}
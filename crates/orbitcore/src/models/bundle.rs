@@ -0,0 +1,23 @@
+use serde_derive::{Deserialize, Serialize};
+
+use super::manifest::Save;
+
+/// `Bundle.toml` at the start of a save bundle.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Bundle {
+    pub name: String,
+    pub product_id: u32,
+    #[serde(default)]
+    pub saves: Vec<BundleSave>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BundleSave {
+    #[serde(flatten)]
+    pub save: Save,
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the save data.
+    pub sha256: String,
+}
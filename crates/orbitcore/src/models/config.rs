@@ -0,0 +1,179 @@
+use serde_derive::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Config {
+    pub orbit: Orbit,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Orbit {
+    pub name: String,
+    pub product_id: u32,
+    /// Products allowed to use the saves, besides `product_id`. With
+    /// `product_id` 0, every product is allowed.
+    #[serde(default)]
+    pub product_ids: Vec<u32>,
+    pub saves: String,
+    pub cd_keys: Vec<String>,
+    /// CD keys of other products, such as DLC.
+    #[serde(default)]
+    pub product_cd_keys: Vec<ProductCdKeys>,
+    pub log: Log,
+    pub profile: Profile,
+    #[serde(default)]
+    pub launcher: Launcher,
+    #[serde(default)]
+    pub quota: Quota,
+    #[serde(default)]
+    pub storage: Storage,
+    #[serde(default)]
+    pub trash: Trash,
+    /// Syncs the saves with a server when set.
+    #[serde(default)]
+    pub sync: Option<CloudSync>,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            product_id: 0,
+            product_ids: Default::default(),
+            saves: "<default>".to_owned(),
+            cd_keys: Default::default(),
+            product_cd_keys: Default::default(),
+            log: Default::default(),
+            profile: Default::default(),
+            launcher: Default::default(),
+            quota: Default::default(),
+            storage: Default::default(),
+            trash: Default::default(),
+            sync: Default::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ProductCdKeys {
+    pub product_id: u32,
+    pub cd_keys: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Log {
+    pub write: bool,
+    pub path: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Profile {
+    pub account_id: String,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Launcher {
+    /// Value returned from `StartLauncher`.
+    pub result: bool,
+    /// Program and arguments started from `StartLauncher`, if any.
+    pub command: Vec<String>,
+    /// Whether `StartProcess` starts the program the game asks for.
+    pub start_process: bool,
+}
+
+/// Limits on the saves of each product. Unset limits are unlimited.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Quota {
+    pub max_slots: Option<u32>,
+    pub max_save_bytes: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+    pub min_id: Option<u32>,
+    pub max_id: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Storage {
+    pub backend: Backend,
+    /// Serves the saves but refuses every change, as if every save was
+    /// locked.
+    pub read_only: bool,
+    /// Starts from the saves on disk but keeps every change in memory, where
+    /// it is lost when the game exits. Also set by the `ORBIT_SANDBOX`
+    /// environment variable.
+    pub sandbox: bool,
+    /// Saves of at least this many bytes are memory-mapped when the game
+    /// reads them, instead of read from the file.
+    pub mmap_threshold: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Backend {
+    #[default]
+    Filesystem,
+    Memory,
+    /// Saves, names and metadata in one database under the saves path.
+    Sqlite,
+}
+
+/// Where removed saves go until they are restored or purged.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct Trash {
+    /// Moves removed saves to a `Trash` folder under the saves path instead of
+    /// deleting them.
+    pub enabled: bool,
+    /// Days a removed save is kept. 0 keeps it until it is restored.
+    pub max_age_days: u64,
+}
+
+impl Default for Trash {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CloudSync {
+    /// Folder on the server, such as `http://nas.local:8080/saves`.
+    pub url: String,
+    #[serde(default)]
+    pub protocol: Protocol,
+    #[serde(default)]
+    pub conflict: Conflict,
+    /// Sent as the `Authorization` header of every request.
+    #[serde(default)]
+    pub authorization: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Protocol {
+    #[default]
+    Http,
+    /// Also creates the product folders with `MKCOL`.
+    WebDav,
+}
+
+/// What to do with a save changed both here and on the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Conflict {
+    /// Logs the conflict and leaves both copies alone.
+    #[default]
+    Ask,
+    /// Keeps the local save and stores the server's copy under a new id.
+    KeepBoth,
+    /// Keeps the copy modified last.
+    NewestWins,
+}
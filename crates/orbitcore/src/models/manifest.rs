@@ -6,9 +6,12 @@ pub struct Manifest {
     pub saves: Vec<Save>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Save {
     pub id: u32,
     pub name: String,
+    /// Refuses writers and removal of the save, which can still be read.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
 }
@@ -0,0 +1,5 @@
+pub mod bundle;
+pub mod config;
+pub mod manifest;
+pub mod sync;
+pub mod trash;
@@ -0,0 +1,60 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// `Index.toml` next to the saves of a product on the server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Index {
+    #[serde(default)]
+    pub saves: Vec<RemoteSave>,
+    /// The version the index was read at, so writes notice other machines.
+    #[serde(skip)]
+    pub version: Version,
+}
+
+/// The version of a file on the server that a write expects to replace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Version {
+    /// Unknown since the last write, the file has to be read again.
+    #[default]
+    Stale,
+    /// The file does not exist.
+    Missing,
+    /// The server's `ETag` for the file.
+    Tag(String),
+    /// The server sends no `ETag`, so writes replace the file unchecked.
+    Untagged,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RemoteSave {
+    pub id: u32,
+    pub name: String,
+    pub revision: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: u64,
+    /// Kept so other machines remove their copy too.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// `Sync.toml` in the saves folder, with the server revision of each save.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SyncState {
+    #[serde(default)]
+    pub saves: Vec<SyncedSave>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SyncedSave {
+    pub product_id: u32,
+    pub id: u32,
+    pub revision: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: u64,
+    /// Changed or removed here since `revision`.
+    #[serde(default)]
+    pub changed: bool,
+}
@@ -0,0 +1,21 @@
+use serde_derive::{Deserialize, Serialize};
+
+/// `Trash.toml` in the `Trash` folder, with the saves kept there.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TrashIndex {
+    #[serde(default)]
+    pub saves: Vec<TrashedSave>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TrashedSave {
+    /// The data is in `<number>.save` of the `Trash` folder.
+    pub number: u32,
+    pub product_id: u32,
+    pub id: u32,
+    pub name: String,
+    /// Seconds since the Unix epoch.
+    pub removed: u64,
+}
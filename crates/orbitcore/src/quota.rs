@@ -0,0 +1,398 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::{fmt, io};
+
+use anyhow::{Error, Result};
+
+use crate::models::config::Quota;
+use crate::storage::SaveStorage;
+
+/// A save with an open writer, counted as it will be once committed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingWrite {
+    pub id: u32,
+    pub truncate: bool,
+    /// Bytes written so far.
+    pub cursor: u64,
+}
+
+/// The open writers of a client, shared with each writer so that every
+/// write counts what the others have written so far.
+#[derive(Debug, Default)]
+pub struct PendingWrites(RefCell<BTreeMap<(Option<u32>, u32), PendingWrite>>);
+
+impl PendingWrites {
+    /// The open writers of `product`.
+    pub fn get(&self, product: Option<u32>) -> Vec<PendingWrite> {
+        self.0
+            .borrow()
+            .iter()
+            .filter(|((write_product, _), _)| *write_product == product)
+            .map(|(_, write)| *write)
+            .collect()
+    }
+
+    #[inline]
+    pub fn insert(&self, product: Option<u32>, write: PendingWrite) {
+        self.0.borrow_mut().insert((product, write.id), write);
+    }
+
+    #[inline]
+    pub fn remove(&self, product: Option<u32>, save_game_id: u32) {
+        self.0.borrow_mut().remove(&(product, save_game_id));
+    }
+}
+
+/// The sizes of the saves of `product`, with the saves of open writers as
+/// they will be once committed.
+fn get_sizes(
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+    pending: &[PendingWrite],
+) -> Result<BTreeMap<u32, u64>> {
+    let mut sizes = storage
+        .list(product)?
+        .into_iter()
+        .map(|save| (save.id, save.size))
+        .collect::<BTreeMap<_, _>>();
+
+    for write in pending {
+        let bytes = get_expected_bytes(sizes.get(&write.id).copied(), write.truncate, write.cursor);
+        sizes.insert(write.id, bytes);
+    }
+
+    Ok(sizes)
+}
+
+/// The size of a save after writing `cursor` bytes from its start.
+#[inline]
+fn get_expected_bytes(existing: Option<u64>, truncate: bool, cursor: u64) -> u64 {
+    match existing {
+        Some(existing) if !truncate => existing.max(cursor),
+        _ => cursor,
+    }
+}
+
+impl Quota {
+    fn is_limited(&self) -> bool {
+        self.max_slots.is_some() || self.max_save_bytes.is_some() || self.max_total_bytes.is_some()
+    }
+
+    pub fn check_id(&self, save_game_id: u32) -> Result<()> {
+        let min_id = self.min_id.unwrap_or(u32::MIN);
+        let max_id = self.max_id.unwrap_or(u32::MAX);
+
+        if !(min_id..=max_id).contains(&save_game_id) {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Savegame id {} is outside {}..={}",
+                    save_game_id, min_id, max_id
+                ),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Checks a new writer against the limits. The saves of other open
+    /// writers count as they are so far, and a save that is not truncated
+    /// counts with its current size.
+    pub fn check_writer(
+        &self,
+        storage: &dyn SaveStorage,
+        product: Option<u32>,
+        save_game_id: u32,
+        truncate: bool,
+        pending: &[PendingWrite],
+    ) -> Result<()> {
+        self.check_id(save_game_id)?;
+
+        if !self.is_limited() {
+            return Ok(());
+        }
+
+        let mut sizes = get_sizes(storage, product, pending)?;
+        let existing = sizes.remove(&save_game_id);
+
+        if let Some(max_slots) = self.max_slots {
+            if existing.is_none() && sizes.len() >= max_slots as usize {
+                return Err(out_of_space(format!(
+                    "All {} savegame slots are used",
+                    max_slots
+                )));
+            }
+        }
+
+        if let Some(existing) = existing.filter(|_| !truncate) {
+            self.check_bytes(save_game_id, existing, sizes.values().sum())?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks a write that brings a save to `write.cursor` bytes against the
+    /// byte limits, counting the other open writers as they are so far.
+    pub fn check_write(
+        &self,
+        storage: &dyn SaveStorage,
+        product: Option<u32>,
+        write: PendingWrite,
+        pending: &[PendingWrite],
+    ) -> Result<()> {
+        if self.max_save_bytes.is_none() && self.max_total_bytes.is_none() {
+            return Ok(());
+        }
+
+        let mut sizes = get_sizes(storage, product, pending)?;
+        let existing = sizes.remove(&write.id);
+        let bytes = get_expected_bytes(existing, write.truncate, write.cursor);
+
+        self.check_bytes(write.id, bytes, sizes.values().sum())
+    }
+
+    fn check_bytes(&self, save_game_id: u32, bytes: u64, other_bytes: u64) -> Result<()> {
+        if let Some(max_save_bytes) = self.max_save_bytes {
+            if bytes > max_save_bytes {
+                return Err(out_of_space(format!(
+                    "Savegame {} would have {} bytes, more than the {} a save may have",
+                    save_game_id, bytes, max_save_bytes
+                )));
+            }
+        }
+
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            if other_bytes.saturating_add(bytes) > max_total_bytes {
+                return Err(out_of_space(format!(
+                    "Savegame {} would have {} bytes, more than the {} left of {}",
+                    save_game_id,
+                    bytes,
+                    max_total_bytes.saturating_sub(other_bytes),
+                    max_total_bytes
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn usage(&self, sizes: impl IntoIterator<Item = u64>) -> QuotaUsage<'_> {
+        let (slots, bytes) = sizes
+            .into_iter()
+            .fold((0, 0), |(slots, bytes), size| (slots + 1, bytes + size));
+
+        QuotaUsage {
+            quota: self,
+            slots,
+            bytes,
+        }
+    }
+}
+
+#[inline]
+pub fn out_of_space(message: String) -> Error {
+    Error::new(io::Error::new(io::ErrorKind::StorageFull, message))
+}
+
+pub struct QuotaUsage<'a> {
+    quota: &'a Quota,
+    pub slots: u32,
+    pub bytes: u64,
+}
+
+impl fmt::Display for QuotaUsage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.slots == 1 { "" } else { "s" };
+        write!(f, "{} slot{}", self.slots, plural)?;
+
+        if let Some(max_slots) = self.quota.max_slots {
+            write!(f, " of {}", max_slots)?;
+        }
+
+        write!(f, ", {} bytes", self.bytes)?;
+
+        if let Some(max_total_bytes) = self.quota.max_total_bytes {
+            write!(f, " of {}", max_total_bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn save(storage: &MemoryStorage, id: u32, data: &[u8]) {
+        let mut save = storage.open_write(None, id, true).unwrap();
+        save.write_at(0, data).unwrap();
+        storage.commit(None, id, save).unwrap();
+        storage.set_name(None, id, &id.to_string()).unwrap();
+    }
+
+    #[test]
+    fn unlimited_by_default() {
+        let quota = Quota::default();
+
+        assert!(quota.check_id(u32::MAX).is_ok());
+        assert!(quota
+            .check_writer(&MemoryStorage::default(), None, u32::MAX, true, &[])
+            .is_ok());
+    }
+
+    #[test]
+    fn id_range() {
+        let quota = Quota {
+            min_id: Some(1),
+            max_id: Some(3),
+            ..Default::default()
+        };
+
+        assert!(quota.check_id(0).is_err());
+        assert!(quota.check_id(1).is_ok());
+        assert!(quota.check_id(3).is_ok());
+        assert!(quota.check_id(4).is_err());
+    }
+
+    #[test]
+    fn slot_limit_allows_overwrites() {
+        let storage = MemoryStorage::default();
+        save(&storage, 1, b"1");
+        save(&storage, 2, b"2");
+
+        let quota = Quota {
+            max_slots: Some(2),
+            ..Default::default()
+        };
+
+        assert!(quota.check_writer(&storage, None, 2, true, &[]).is_ok());
+        assert!(quota.check_writer(&storage, None, 3, true, &[]).is_err());
+    }
+
+    fn write(id: u32, truncate: bool, cursor: u64) -> PendingWrite {
+        PendingWrite {
+            id,
+            truncate,
+            cursor,
+        }
+    }
+
+    #[test]
+    fn byte_limits() {
+        let storage = MemoryStorage::default();
+        save(&storage, 1, b"123456");
+        save(&storage, 2, b"12");
+
+        let quota = Quota {
+            max_save_bytes: Some(5),
+            max_total_bytes: Some(10),
+            ..Default::default()
+        };
+
+        assert!(quota
+            .check_write(&storage, None, write(1, true, 5), &[])
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(1, true, 6), &[])
+            .is_err());
+        assert!(quota
+            .check_write(&storage, None, write(2, true, 4), &[])
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(2, true, 5), &[])
+            .is_err());
+        assert!(quota
+            .check_write(&storage, None, write(3, true, 2), &[])
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(3, true, 3), &[])
+            .is_err());
+    }
+
+    #[test]
+    fn open_writers_count() {
+        let storage = MemoryStorage::default();
+        save(&storage, 1, b"1234");
+
+        let quota = Quota {
+            max_slots: Some(3),
+            max_total_bytes: Some(10),
+            ..Default::default()
+        };
+        let pending = [write(1, false, 2), write(2, true, 3)];
+
+        assert!(quota
+            .check_writer(&storage, None, 3, true, &pending)
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(3, true, 3), &pending)
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(3, true, 4), &pending)
+            .is_err());
+        assert!(quota
+            .check_writer(&storage, None, 4, true, &[pending[1], write(3, true, 0)])
+            .is_err());
+    }
+
+    #[test]
+    fn writes_count_what_other_writers_wrote_since() {
+        let storage = MemoryStorage::default();
+
+        let quota = Quota {
+            max_total_bytes: Some(10),
+            ..Default::default()
+        };
+
+        assert!(quota.check_writer(&storage, None, 1, true, &[]).is_ok());
+        assert!(quota
+            .check_writer(&storage, None, 2, true, &[write(1, true, 0)])
+            .is_ok());
+
+        assert!(quota
+            .check_write(&storage, None, write(1, true, 8), &[write(2, true, 0)])
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(2, true, 3), &[write(1, true, 8)])
+            .is_err());
+    }
+
+    #[test]
+    fn appending_counts_the_existing_size() {
+        let storage = MemoryStorage::default();
+        save(&storage, 1, b"123456");
+
+        let quota = Quota {
+            max_save_bytes: Some(5),
+            ..Default::default()
+        };
+
+        assert!(quota.check_writer(&storage, None, 1, true, &[]).is_ok());
+        assert!(quota.check_writer(&storage, None, 1, false, &[]).is_err());
+        assert!(quota
+            .check_write(&storage, None, write(1, true, 2), &[])
+            .is_ok());
+        assert!(quota
+            .check_write(&storage, None, write(1, false, 2), &[])
+            .is_err());
+    }
+
+    #[test]
+    fn usage() {
+        let quota = Quota {
+            max_slots: Some(4),
+            max_total_bytes: Some(100),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            quota.usage(vec![10, 20]).to_string(),
+            "2 slots of 4, 30 bytes of 100"
+        );
+        assert_eq!(
+            Quota::default().usage(vec![5]).to_string(),
+            "1 slot, 5 bytes"
+        );
+    }
+}
@@ -0,0 +1,600 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use derive_new::new;
+use log::{debug, error};
+use memmap2::Mmap;
+
+use super::{SaveRead, SaveStat, SaveStorage, SaveWrite};
+use crate::helpers::manifest::{get_manifest_stamp, read_manifest, update_manifest};
+use crate::helpers::save::{
+    get_product_saves_path, get_save_path, open_save, read_save_at, write_save_at,
+};
+use crate::models::manifest::{Manifest, Save};
+
+/// Saves created by a writer that has not committed yet.
+type Uncommitted = Rc<RefCell<BTreeSet<(Option<u32>, u32)>>>;
+
+/// `<id>.save` files next to a `Manifest.toml` with their names. Other
+/// products get a `<product id>` subfolder. Each manifest is read once and
+/// changes to it are kept until `flush`, or until the storage is dropped.
+#[derive(Debug, new)]
+pub struct FsStorage {
+    pub saves_path: PathBuf,
+    /// Saves of at least this many bytes are memory-mapped when read.
+    #[new(default)]
+    pub mmap_threshold: Option<u64>,
+    #[new(default)]
+    manifests: RefCell<BTreeMap<Option<u32>, CachedManifest>>,
+    #[new(default)]
+    uncommitted: Uncommitted,
+}
+
+#[derive(Debug)]
+enum ManifestChange {
+    SetName(u32, String),
+    SetLocked(u32, bool),
+    Remove(u32),
+}
+
+impl ManifestChange {
+    fn apply(&self, manifest: &mut Manifest) {
+        match self {
+            Self::SetName(id, name) => {
+                match manifest.saves.iter_mut().find(|save| save.id == *id) {
+                    Some(save) => save.name = name.clone(),
+                    None => manifest.saves.push(Save {
+                        id: *id,
+                        name: name.clone(),
+                        locked: false,
+                    }),
+                }
+            }
+            Self::SetLocked(id, locked) => {
+                if let Some(save) = manifest.saves.iter_mut().find(|save| save.id == *id) {
+                    save.locked = *locked;
+                }
+            }
+            Self::Remove(id) => manifest.saves.retain(|save| save.id != *id),
+        }
+    }
+}
+
+/// A manifest as last read from disk, with the changes not written yet.
+#[derive(Debug, Default)]
+struct CachedManifest {
+    loaded: bool,
+    stamp: Option<(SystemTime, u64)>,
+    manifest: Manifest,
+    changes: Vec<ManifestChange>,
+}
+
+impl CachedManifest {
+    /// Rereads the manifest if it was never read or was changed on disk,
+    /// keeping the changes made here on top.
+    fn refresh(&mut self, saves_path: &Path) -> Result<()> {
+        let stamp = get_manifest_stamp(saves_path)?;
+
+        if self.loaded && self.stamp == stamp {
+            return Ok(());
+        }
+
+        let mut manifest = match stamp {
+            Some(_) => read_manifest(saves_path)?,
+            None => Manifest::default(),
+        };
+
+        for change in &self.changes {
+            change.apply(&mut manifest);
+        }
+
+        self.loaded = true;
+        self.stamp = stamp;
+        self.manifest = manifest;
+
+        Ok(())
+    }
+
+    fn change(&mut self, change: ManifestChange) {
+        change.apply(&mut self.manifest);
+        self.changes.push(change);
+    }
+
+    fn flush(&mut self, saves_path: &Path) -> Result<()> {
+        if self.changes.is_empty() {
+            return Ok(());
+        }
+
+        let manifest = update_manifest(saves_path, |manifest| {
+            for change in &self.changes {
+                change.apply(manifest);
+            }
+
+            Ok(manifest.clone())
+        })?;
+
+        self.changes.clear();
+        self.stamp = get_manifest_stamp(saves_path)?;
+        self.manifest = manifest;
+
+        Ok(())
+    }
+}
+
+impl FsStorage {
+    pub fn get_product_path(&self, product: Option<u32>) -> PathBuf {
+        match product {
+            Some(product_id) => get_product_saves_path(&self.saves_path, product_id),
+            None => self.saves_path.clone(),
+        }
+    }
+
+    /// Locks or unlocks a save that has a name, kept until `flush` like
+    /// other manifest changes.
+    pub fn set_locked(&self, product: Option<u32>, id: u32, locked: bool) -> Result<()> {
+        self.with_manifest(product, |cached| {
+            cached.change(ManifestChange::SetLocked(id, locked));
+        })
+    }
+
+    fn with_manifest<T>(
+        &self,
+        product: Option<u32>,
+        f: impl FnOnce(&mut CachedManifest) -> T,
+    ) -> Result<T> {
+        let mut manifests = self.manifests.borrow_mut();
+        let cached = manifests.entry(product).or_default();
+
+        cached.refresh(&self.get_product_path(product))?;
+
+        Ok(f(cached))
+    }
+}
+
+#[inline]
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<io::Error>().map(io::Error::kind) == Some(io::ErrorKind::NotFound)
+}
+
+#[derive(Debug)]
+struct FsRead {
+    file: File,
+}
+
+impl SaveRead for FsRead {
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        read_save_at(&mut self.file, offset, buffer)
+    }
+}
+
+/// A save mapped into memory, copied from the mapping straight into the
+/// buffer of the reader. The file is kept open for its shared lock.
+#[derive(Debug)]
+struct MappedRead {
+    map: Mmap,
+    _file: File,
+}
+
+impl SaveRead for MappedRead {
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let start = (offset as usize).min(self.map.len());
+        let size = buffer.len().min(self.map.len() - start);
+
+        buffer[..size].copy_from_slice(&self.map[start..start + size]);
+
+        Ok(size)
+    }
+}
+
+/// A save created by a writer, hidden until the writer commits and removed
+/// again if it never does.
+#[derive(Debug)]
+struct NewSave {
+    uncommitted: Uncommitted,
+    key: (Option<u32>, u32),
+    path: PathBuf,
+}
+
+impl Drop for NewSave {
+    fn drop(&mut self) {
+        if !self.uncommitted.borrow_mut().remove(&self.key) {
+            return;
+        }
+
+        if let Err(err) = fs::remove_file(&self.path) {
+            error!("Could not remove {}: {}", self.path.display(), err);
+        }
+    }
+}
+
+/// The file is declared first, so it is closed before a `NewSave` that was
+/// never committed removes it.
+#[derive(Debug)]
+struct FsWrite {
+    file: File,
+    new_save: Option<NewSave>,
+}
+
+impl SaveWrite for FsWrite {
+    fn write_at(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        write_save_at(&mut self.file, offset, buffer)
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.file.sync_all()?;
+
+        if let Some(new_save) = &self.new_save {
+            new_save.uncommitted.borrow_mut().remove(&new_save.key);
+        }
+
+        Ok(())
+    }
+}
+
+impl SaveStorage for FsStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        let path = self.get_product_path(product);
+        let mut saves = self.with_manifest(product, |cached| cached.manifest.saves.clone())?;
+        let mut stats = Vec::new();
+
+        saves.sort_unstable_by_key(|save| save.id);
+        saves.retain(|save| !self.uncommitted.borrow().contains(&(product, save.id)));
+
+        for save in saves {
+            match fs::metadata(get_save_path(&path, save.id)) {
+                Ok(metadata) if metadata.is_file() => stats.push(SaveStat {
+                    id: save.id,
+                    name: save.name,
+                    size: metadata.len(),
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(stats)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        let path = get_save_path(&self.get_product_path(product), id);
+        let file = File::open(path)?;
+
+        if let Some(threshold) = self.mmap_threshold {
+            let size = file.metadata()?.len();
+
+            if size > 0 && size >= threshold {
+                match file.try_lock_shared() {
+                    Ok(()) => {
+                        // SAFETY: Writers of this storage take an exclusive
+                        // lock before they write or truncate a save, so they
+                        // wait for the shared lock held as long as the mapping.
+                        // Windows also refuses to truncate a mapped file. Other
+                        // systems only advise locks, so a program that ignores
+                        // them could still shrink the save under the mapping.
+                        let map = unsafe { Mmap::map(&file)? };
+
+                        return Ok(Box::new(MappedRead { map, _file: file }));
+                    }
+                    Err(TryLockError::WouldBlock) => {
+                        debug!("Savegame {} is being written, not mapping it", id)
+                    }
+                    Err(TryLockError::Error(err)) => return Err(err.into()),
+                }
+            }
+        }
+
+        Ok(Box::new(FsRead { file }))
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        let path = get_save_path(&self.get_product_path(product), id);
+        let options = OpenOptions::new().read(true).write(true).to_owned();
+
+        // Only a save that is already there can be locked by a reader, so a
+        // busy save is never created. A new one stays hidden until committed.
+        let (file, new_save) = match open_save(&path, &options) {
+            Ok(file) => (file, None),
+            Err(err) if is_not_found(&err) => {
+                let file = open_save(&path, options.clone().create_new(true))?;
+                self.uncommitted.borrow_mut().insert((product, id));
+
+                let new_save = NewSave {
+                    uncommitted: self.uncommitted.clone(),
+                    key: (product, id),
+                    path,
+                };
+
+                (file, Some(new_save))
+            }
+            Err(err) => return Err(err),
+        };
+
+        // Mapped readers hold a shared lock, so the save is only truncated
+        // once they are gone.
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::ResourceBusy,
+                    format!("Savegame {} is being read", id),
+                )
+                .into())
+            }
+            Err(TryLockError::Error(err)) => return Err(err.into()),
+        }
+
+        if truncate {
+            file.set_len(0)?;
+        }
+
+        Ok(Box::new(FsWrite { file, new_save }))
+    }
+
+    fn commit(&self, _product: Option<u32>, _id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        save.finish()
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        fs::remove_file(get_save_path(&self.get_product_path(product), id))?;
+
+        self.with_manifest(product, |cached| {
+            cached.change(ManifestChange::Remove(id));
+        })
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        self.with_manifest(product, |cached| {
+            cached.change(ManifestChange::SetName(id, name.to_owned()));
+        })
+    }
+
+    fn is_locked(&self, product: Option<u32>, id: u32) -> Result<bool> {
+        self.with_manifest(product, |cached| {
+            cached
+                .manifest
+                .saves
+                .iter()
+                .any(|save| save.id == id && save.locked)
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        for (product, cached) in self.manifests.borrow_mut().iter_mut() {
+            cached.flush(&self.get_product_path(*product))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for FsStorage {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            error!("Could not write the names of the saves: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::consts::SAVES_MANIFEST_NAME;
+
+    #[test]
+    fn keeps_the_folder_layout() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+
+        let mut save = storage.open_write(None, 1, true).unwrap();
+        save.write_at(0, b"game").unwrap();
+        storage.commit(None, 1, save).unwrap();
+        storage.set_name(None, 1, "Game").unwrap();
+
+        let mut save = storage.open_write(Some(200), 1, true).unwrap();
+        save.write_at(0, b"dlc").unwrap();
+        storage.commit(Some(200), 1, save).unwrap();
+        storage.set_name(Some(200), 1, "DLC").unwrap();
+
+        assert_eq!(fs::read(dir.path().join("1.save")).unwrap(), b"game");
+        assert_eq!(
+            fs::read(dir.path().join("200").join("1.save")).unwrap(),
+            b"dlc"
+        );
+        assert_eq!(
+            storage.stat(Some(200), 1).unwrap(),
+            Some(SaveStat {
+                id: 1,
+                name: "DLC".to_owned(),
+                size: 3
+            })
+        );
+    }
+
+    fn write(storage: &FsStorage, id: u32, name: &str) {
+        let mut save = storage.open_write(None, id, true).unwrap();
+        save.write_at(0, name.as_bytes()).unwrap();
+        storage.commit(None, id, save).unwrap();
+        storage.set_name(None, id, name).unwrap();
+    }
+
+    fn names(storage: &FsStorage) -> Vec<String> {
+        storage
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|save| save.name)
+            .collect()
+    }
+
+    #[test]
+    fn names_are_written_on_flush() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+
+        write(&storage, 1, "First");
+        write(&storage, 2, "Second");
+        storage.remove(None, 2).unwrap();
+
+        assert_eq!(names(&storage), vec!["First"]);
+        assert!(!dir.path().join(SAVES_MANIFEST_NAME).exists());
+
+        storage.flush().unwrap();
+
+        assert_eq!(names(&FsStorage::new(dir.path().to_owned())), vec!["First"]);
+
+        storage.set_name(None, 1, "Renamed").unwrap();
+        drop(storage);
+
+        assert_eq!(
+            names(&FsStorage::new(dir.path().to_owned())),
+            vec!["Renamed"]
+        );
+    }
+
+    #[test]
+    fn edits_by_other_processes_are_noticed() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+        let other = FsStorage::new(dir.path().to_owned());
+
+        write(&storage, 1, "Mine");
+        storage.flush().unwrap();
+        write(&storage, 2, "Unsaved");
+
+        write(&other, 3, "Theirs");
+        other.flush().unwrap();
+
+        assert_eq!(names(&storage), vec!["Mine", "Unsaved", "Theirs"]);
+
+        storage.flush().unwrap();
+
+        assert_eq!(
+            names(&FsStorage::new(dir.path().to_owned())),
+            vec!["Mine", "Unsaved", "Theirs"]
+        );
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn big_saves_are_mapped() {
+        let dir = tempdir().unwrap();
+        let mut storage = FsStorage::new(dir.path().to_owned());
+        storage.mmap_threshold = Some(6);
+
+        write(&storage, 1, "Small");
+        write(&storage, 2, "Bigger save");
+
+        let mut buffer = [0u8; 8];
+        let mut small = storage.open_read(None, 1).unwrap();
+        let mut big = storage.open_read(None, 2).unwrap();
+
+        assert!(format!("{:?}", small).starts_with("FsRead"));
+        assert!(format!("{:?}", big).starts_with("MappedRead"));
+        assert_eq!(small.read_at(1, &mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], b"mall");
+        assert_eq!(big.read_at(7, &mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], b"save");
+        assert_eq!(big.read_at(100, &mut buffer).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn mapped_saves_are_not_truncated() {
+        let dir = tempdir().unwrap();
+        let mut storage = FsStorage::new(dir.path().to_owned());
+        storage.mmap_threshold = Some(1);
+
+        write(&storage, 1, "Mapped");
+
+        let mut mapped = storage.open_read(None, 1).unwrap();
+        let err = storage.open_write(None, 1, true).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::ResourceBusy)
+        );
+
+        let mut buffer = [0u8; 6];
+
+        assert_eq!(mapped.read_at(0, &mut buffer).unwrap(), 6);
+        assert_eq!(&buffer, b"Mapped");
+
+        drop(mapped);
+        write(&storage, 1, "New");
+
+        let mut writer = storage.open_write(None, 1, false).unwrap();
+
+        assert!(format!("{:?}", storage.open_read(None, 1).unwrap()).starts_with("FsRead"));
+
+        writer.write_at(0, b"n").unwrap();
+        storage.commit(None, 1, writer).unwrap();
+
+        assert_eq!(fs::read(dir.path().join("1.save")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn lists_only_saves_in_the_manifest() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().join("Saves"));
+
+        assert!(storage.list(None).unwrap().is_empty());
+
+        write(&storage, 1, "First");
+        fs::write(dir.path().join("Saves").join("2.save"), b"2").unwrap();
+
+        assert_eq!(names(&storage), vec!["First"]);
+    }
+
+    #[test]
+    fn open_missing_save_is_an_error() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+
+        assert!(storage.open_read(None, 1).is_err());
+    }
+
+    #[test]
+    fn new_saves_are_hidden_until_committed() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+        write(&storage, 1, "First");
+
+        let mut save = storage.open_write(None, 2, true).unwrap();
+        save.write_at(0, b"2").unwrap();
+        storage.set_name(None, 2, "Second").unwrap();
+
+        assert_eq!(names(&storage), vec!["First"]);
+
+        storage.commit(None, 2, save).unwrap();
+
+        assert_eq!(names(&storage), vec!["First", "Second"]);
+
+        let save = storage.open_write(None, 3, true).unwrap();
+        storage.set_name(None, 3, "Third").unwrap();
+        drop(save);
+
+        assert!(!dir.path().join("3.save").exists());
+        assert_eq!(names(&storage), vec!["First", "Second"]);
+    }
+
+    #[test]
+    fn remove_missing_save_is_an_error() {
+        let dir = tempdir().unwrap();
+        let storage = FsStorage::new(dir.path().to_owned());
+        storage.set_name(None, 1, "First").unwrap();
+
+        assert!(storage.remove(None, 1).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use anyhow::Result;
+use derive_new::new;
+
+use super::{not_found, SaveRead, SaveStat, SaveStorage, SaveWrite};
+
+type Saves = Rc<RefCell<BTreeMap<(Option<u32>, u32), MemorySave>>>;
+
+#[derive(Debug, Default)]
+struct MemorySave {
+    name: Option<String>,
+    /// `None` until a writer commits, so a save named before its first
+    /// commit is not there yet.
+    data: Option<Rc<Vec<u8>>>,
+}
+
+/// Saves kept in memory for the lifetime of the storage.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    saves: Saves,
+}
+
+#[derive(Debug, new)]
+pub(super) struct MemoryRead {
+    data: Rc<Vec<u8>>,
+}
+
+impl SaveRead for MemoryRead {
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let start = (offset as usize).min(self.data.len());
+        let size = buffer.len().min(self.data.len() - start);
+
+        buffer[..size].copy_from_slice(&self.data[start..start + size]);
+
+        Ok(size)
+    }
+}
+
+#[derive(Debug)]
+struct MemoryWrite {
+    saves: Saves,
+    key: (Option<u32>, u32),
+    data: Vec<u8>,
+}
+
+impl SaveWrite for MemoryWrite {
+    fn write_at(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        write_data_at(&mut self.data, offset, buffer);
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut saves = self.saves.borrow_mut();
+        saves.entry(self.key).or_default().data = Some(Rc::new(self.data));
+
+        Ok(())
+    }
+}
+
+/// Writes `buffer` at `offset`, growing `data` with zeros as needed.
+#[inline]
+pub(super) fn write_data_at(data: &mut Vec<u8>, offset: u64, buffer: &[u8]) {
+    let start = offset as usize;
+    let end = start + buffer.len();
+
+    if data.len() < end {
+        data.resize(end, 0);
+    }
+
+    data[start..end].copy_from_slice(buffer);
+}
+
+impl SaveStorage for MemoryStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        let saves = self
+            .saves
+            .borrow()
+            .iter()
+            .filter(|((save_product, _), _)| *save_product == product)
+            .filter_map(|((_, id), save)| match (&save.name, &save.data) {
+                (Some(name), Some(data)) => Some(SaveStat {
+                    id: *id,
+                    name: name.clone(),
+                    size: data.len() as u64,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        Ok(saves)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        let saves = self.saves.borrow();
+        let data = saves
+            .get(&(product, id))
+            .and_then(|save| save.data.clone())
+            .ok_or_else(|| not_found(id))?;
+
+        Ok(Box::new(MemoryRead::new(data)))
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        let data = match self.saves.borrow().get(&(product, id)) {
+            Some(MemorySave {
+                data: Some(data), ..
+            }) if !truncate => data.as_ref().clone(),
+            _ => Vec::new(),
+        };
+
+        Ok(Box::new(MemoryWrite {
+            saves: self.saves.clone(),
+            key: (product, id),
+            data,
+        }))
+    }
+
+    fn commit(&self, _product: Option<u32>, _id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        save.finish()
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        self.saves
+            .borrow_mut()
+            .remove(&(product, id))
+            .ok_or_else(|| not_found(id))?;
+
+        Ok(())
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        let mut saves = self.saves.borrow_mut();
+        saves.entry((product, id)).or_default().name = Some(name.to_owned());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(storage: &MemoryStorage, id: u32, truncate: bool, data: &[u8]) {
+        let mut save = storage.open_write(None, id, truncate).unwrap();
+        save.write_at(0, data).unwrap();
+        storage.commit(None, id, save).unwrap();
+    }
+
+    fn read(storage: &MemoryStorage, id: u32, offset: u64, len: usize) -> Vec<u8> {
+        let mut buffer = vec![0u8; len];
+        let size = storage
+            .open_read(None, id)
+            .unwrap()
+            .read_at(offset, &mut buffer)
+            .unwrap();
+
+        buffer.truncate(size);
+        buffer
+    }
+
+    #[test]
+    fn round_trip() {
+        let storage = MemoryStorage::default();
+
+        write(&storage, 1, true, b"hello world");
+        storage.set_name(None, 1, "Save").unwrap();
+
+        assert_eq!(read(&storage, 1, 6, 10), b"world");
+        assert!(read(&storage, 1, 100, 4).is_empty());
+        assert_eq!(
+            storage.list(None).unwrap(),
+            vec![SaveStat {
+                id: 1,
+                name: "Save".to_owned(),
+                size: 11
+            }]
+        );
+        assert!(storage.list(Some(200)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn writes_are_visible_after_commit() {
+        let storage = MemoryStorage::default();
+        write(&storage, 1, true, b"hello");
+
+        let mut save = storage.open_write(None, 1, false).unwrap();
+        save.write_at(0, b"J").unwrap();
+
+        assert_eq!(read(&storage, 1, 0, 5), b"hello");
+
+        storage.commit(None, 1, save).unwrap();
+
+        assert_eq!(read(&storage, 1, 0, 5), b"Jello");
+    }
+
+    #[test]
+    fn unnamed_saves_are_not_listed() {
+        let storage = MemoryStorage::default();
+        write(&storage, 1, true, b"1");
+
+        assert!(storage.list(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn saves_are_hidden_until_committed() {
+        let storage = MemoryStorage::default();
+
+        let mut save = storage.open_write(None, 1, true).unwrap();
+        save.write_at(0, b"1").unwrap();
+        storage.set_name(None, 1, "Save").unwrap();
+
+        assert!(storage.list(None).unwrap().is_empty());
+        assert!(storage.open_read(None, 1).is_err());
+
+        storage.commit(None, 1, save).unwrap();
+
+        assert_eq!(storage.list(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn remove_missing_save_is_an_error() {
+        let storage = MemoryStorage::default();
+
+        assert!(storage.remove(None, 1).is_err());
+        assert!(storage.open_read(None, 1).is_err());
+    }
+}
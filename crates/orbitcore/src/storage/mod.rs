@@ -0,0 +1,305 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{env, fmt, io};
+
+use anyhow::{Error, Result};
+use log::info;
+
+use crate::consts::SANDBOX_ENV_NAME;
+use crate::helpers::config::is_sandbox;
+use crate::models::config::{Backend, Orbit, Storage};
+
+pub mod fs;
+pub mod memory;
+pub mod overlay;
+pub mod read_only;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "sync")]
+pub mod sync;
+pub mod trash;
+pub mod unavailable;
+
+pub use self::fs::FsStorage;
+pub use self::memory::MemoryStorage;
+pub use self::overlay::OverlayStorage;
+pub use self::read_only::ReadOnlyStorage;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqliteStorage;
+#[cfg(feature = "sync")]
+pub use self::sync::SyncStorage;
+pub use self::trash::TrashStorage;
+pub use self::unavailable::UnavailableStorage;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveStat {
+    pub id: u32,
+    pub name: String,
+    pub size: u64,
+}
+
+pub trait SaveRead: fmt::Debug {
+    /// Fills `buffer` from `offset`, returning how many bytes were read.
+    fn read_at(&mut self, offset: u64, buffer: &mut [u8]) -> Result<usize>;
+}
+
+pub trait SaveWrite: fmt::Debug {
+    fn write_at(&mut self, offset: u64, buffer: &[u8]) -> Result<()>;
+
+    /// Makes the written data durable and releases the handle.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Where savegames live. `product` is `None` for the configured product and
+/// the product id for any other product.
+pub trait SaveStorage: fmt::Debug {
+    /// Saves that have both data and a name.
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>>;
+
+    fn stat(&self, product: Option<u32>, id: u32) -> Result<Option<SaveStat>> {
+        let save = self.list(product)?.into_iter().find(|save| save.id == id);
+
+        Ok(save)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>>;
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>>;
+
+    fn commit(&self, product: Option<u32>, id: u32, save: Box<dyn SaveWrite>) -> Result<()>;
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()>;
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()>;
+
+    /// Whether the game may only read the save.
+    fn is_locked(&self, _product: Option<u32>, _id: u32) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Writes out changes the storage has kept in memory.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Fetches changes made elsewhere, before the saves are listed. Saves in
+    /// `in_use` have open readers or writers and are left alone.
+    fn pull(&self, _product: Option<u32>, _in_use: &BTreeSet<u32>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[inline]
+pub fn open_storage(orbit: &Orbit, saves_path: PathBuf) -> Result<Rc<dyn SaveStorage>> {
+    let storage = &orbit.storage;
+    let sandbox = is_sandbox(storage, env::var_os(SANDBOX_ENV_NAME).as_deref());
+    let backend = open_backend(storage, &saves_path, sandbox || storage.read_only)?;
+
+    // A sandbox must leave the trash and the sync server alone too.
+    let backend: Rc<dyn SaveStorage> = if sandbox {
+        info!(
+            "Sandbox session, changes to {} are thrown away on exit",
+            saves_path.display()
+        );
+
+        Rc::new(OverlayStorage::new(backend))
+    } else {
+        keep_changes(orbit, backend, &saves_path)?
+    };
+
+    if storage.read_only {
+        return Ok(Rc::new(ReadOnlyStorage::new(backend)));
+    }
+
+    Ok(backend)
+}
+
+/// Opens the configured backend alone, without the sandbox, trash, sync or
+/// read-only layers of a game session. A `snapshot` is only read, so it
+/// creates nothing under the saves path.
+#[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+pub fn open_backend(
+    storage: &Storage,
+    saves_path: &Path,
+    snapshot: bool,
+) -> Result<Rc<dyn SaveStorage>> {
+    let backend: Rc<dyn SaveStorage> = match storage.backend {
+        Backend::Filesystem => {
+            let mut fs_storage = FsStorage::new(saves_path.to_owned());
+            fs_storage.mmap_threshold = storage.mmap_threshold;
+
+            Rc::new(fs_storage)
+        }
+        Backend::Memory => Rc::new(MemoryStorage::default()),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite if snapshot => Rc::new(SqliteStorage::open_snapshot(saves_path)?),
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => Rc::new(SqliteStorage::open(saves_path)?),
+        #[cfg(not(feature = "sqlite"))]
+        Backend::Sqlite => {
+            return Err(Error::msg(
+                "Backend = \"Sqlite\" needs a loader built with the sqlite feature",
+            ))
+        }
+    };
+
+    Ok(backend)
+}
+
+/// Wraps the backend of a normal session, whose changes are kept.
+fn keep_changes(
+    orbit: &Orbit,
+    backend: Rc<dyn SaveStorage>,
+    saves_path: &Path,
+) -> Result<Rc<dyn SaveStorage>> {
+    // Saves in memory are gone when the game exits, so they skip the trash.
+    let backend: Rc<dyn SaveStorage> =
+        if orbit.trash.enabled && orbit.storage.backend != Backend::Memory {
+            Rc::new(TrashStorage::open(
+                backend,
+                &orbit.trash,
+                orbit.product_id,
+                saves_path,
+            ))
+        } else {
+            backend
+        };
+
+    let storage: Rc<dyn SaveStorage> = match &orbit.sync {
+        #[cfg(feature = "sync")]
+        Some(sync) => Rc::new(SyncStorage::open(
+            backend,
+            sync,
+            orbit.product_id,
+            saves_path,
+        )),
+        #[cfg(not(feature = "sync"))]
+        Some(sync) => {
+            return Err(Error::msg(format!(
+                "[Orbit.Sync] with {} needs a loader built with the sync feature",
+                sync.url
+            )))
+        }
+        None => backend,
+    };
+
+    Ok(storage)
+}
+
+/// Copies every listed save of a product, returning how many were copied.
+pub fn copy_saves(
+    from: &dyn SaveStorage,
+    to: &dyn SaveStorage,
+    product: Option<u32>,
+) -> Result<usize> {
+    let saves = from.list(product)?;
+
+    for save in &saves {
+        let data = read_save_data(from, product, save)?;
+        write_save_data(to, product, save.id, &save.name, &data)?;
+    }
+
+    Ok(saves.len())
+}
+
+#[inline]
+pub fn read_save_data(
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+    save: &SaveStat,
+) -> Result<Vec<u8>> {
+    let mut data = vec![0u8; save.size as usize];
+    let size = storage.open_read(product, save.id)?.read_at(0, &mut data)?;
+    data.truncate(size);
+
+    Ok(data)
+}
+
+/// Replaces a save and its name in one commit.
+#[inline]
+pub fn write_save_data(
+    storage: &dyn SaveStorage,
+    product: Option<u32>,
+    id: u32,
+    name: &str,
+    data: &[u8],
+) -> Result<()> {
+    let mut save = storage.open_write(product, id, true)?;
+    save.write_at(0, data)?;
+    storage.set_name(product, id, name)?;
+    storage.commit(product, id, save)
+}
+
+#[inline]
+pub(crate) fn not_found(id: u32) -> Error {
+    Error::new(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Savegame {} not found", id),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::config::Storage;
+
+    #[test]
+    fn opens_configured_backend() {
+        let orbit = Orbit {
+            storage: Storage {
+                backend: Backend::Memory,
+                read_only: true,
+                sandbox: false,
+                mmap_threshold: None,
+            },
+            ..Default::default()
+        };
+
+        let storage = open_storage(&orbit, PathBuf::from("Saves")).unwrap();
+
+        assert!(storage.list(None).unwrap().is_empty());
+        assert!(storage.open_write(None, 1, true).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "sqlite", not(miri)))]
+    fn sandbox_creates_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let saves_path = dir.path().join("Saves");
+        let orbit = Orbit {
+            storage: Storage {
+                backend: Backend::Sqlite,
+                sandbox: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let storage = open_storage(&orbit, saves_path.clone()).unwrap();
+        write_save_data(&*storage, None, 1, "Save", b"data").unwrap();
+
+        assert_eq!(storage.list(None).unwrap().len(), 1);
+        assert!(!saves_path.exists());
+    }
+
+    #[test]
+    fn copies_saves_between_backends() {
+        let from = MemoryStorage::default();
+        let to = MemoryStorage::default();
+
+        let mut save = from.open_write(Some(200), 1, true).unwrap();
+        save.write_at(0, b"data").unwrap();
+        from.commit(Some(200), 1, save).unwrap();
+        from.set_name(Some(200), 1, "Save").unwrap();
+
+        assert_eq!(copy_saves(&from, &to, None).unwrap(), 0);
+        assert_eq!(copy_saves(&from, &to, Some(200)).unwrap(), 1);
+        assert_eq!(to.list(Some(200)).unwrap(), from.list(Some(200)).unwrap());
+    }
+}
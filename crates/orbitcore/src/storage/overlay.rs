@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use super::{
+    not_found, read_save_data, write_save_data, MemoryStorage, SaveRead, SaveStat, SaveStorage,
+    SaveWrite,
+};
+
+/// Serves the saves of another storage as a snapshot. Changes go to a
+/// copy-on-write layer in memory and are thrown away with the storage, so
+/// the saves underneath are never touched.
+#[derive(Debug)]
+pub struct OverlayStorage {
+    base: Rc<dyn SaveStorage>,
+    overlay: MemoryStorage,
+    removed: RefCell<BTreeSet<(Option<u32>, u32)>>,
+}
+
+impl OverlayStorage {
+    pub fn new(base: Rc<dyn SaveStorage>) -> Self {
+        Self {
+            base,
+            overlay: MemoryStorage::default(),
+            removed: Default::default(),
+        }
+    }
+
+    fn is_changed(&self, product: Option<u32>, id: u32) -> bool {
+        self.overlay.open_read(product, id).is_ok()
+    }
+
+    fn is_removed(&self, product: Option<u32>, id: u32) -> bool {
+        self.removed.borrow().contains(&(product, id))
+    }
+
+    /// Copies a save of the snapshot into the overlay before it changes.
+    fn copy_up(&self, product: Option<u32>, id: u32) -> Result<()> {
+        if self.is_changed(product, id) || self.is_removed(product, id) {
+            return Ok(());
+        }
+
+        if let Some(save) = self.base.stat(product, id)? {
+            let data = read_save_data(&*self.base, product, &save)?;
+            write_save_data(&self.overlay, product, id, &save.name, &data)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl SaveStorage for OverlayStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        let mut saves = self
+            .base
+            .list(product)?
+            .into_iter()
+            .filter(|save| !self.is_changed(product, save.id) && !self.is_removed(product, save.id))
+            .collect::<Vec<_>>();
+
+        saves.extend(self.overlay.list(product)?);
+        saves.sort_unstable_by_key(|save| save.id);
+
+        Ok(saves)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        if self.is_changed(product, id) {
+            return self.overlay.open_read(product, id);
+        }
+
+        if self.is_removed(product, id) {
+            return Err(not_found(id));
+        }
+
+        self.base.open_read(product, id)
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        self.copy_up(product, id)?;
+        self.overlay.open_write(product, id, truncate)
+    }
+
+    fn commit(&self, product: Option<u32>, id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        self.overlay.commit(product, id, save)
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        let changed = self.overlay.remove(product, id).is_ok();
+        let in_base = !self.is_removed(product, id) && self.base.stat(product, id)?.is_some();
+
+        if !changed && !in_base {
+            return Err(not_found(id));
+        }
+
+        self.removed.borrow_mut().insert((product, id));
+
+        Ok(())
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        self.copy_up(product, id)?;
+        self.overlay.set_name(product, id, name)
+    }
+
+    fn is_locked(&self, product: Option<u32>, id: u32) -> Result<bool> {
+        self.base.is_locked(product, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saves(storage: &dyn SaveStorage) -> Vec<(u32, String, Vec<u8>)> {
+        storage
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|save| {
+                let data = read_save_data(storage, None, &save).unwrap();
+                (save.id, save.name, data)
+            })
+            .collect()
+    }
+
+    fn save(id: u32, name: &str, data: &[u8]) -> (u32, String, Vec<u8>) {
+        (id, name.to_owned(), data.to_vec())
+    }
+
+    #[test]
+    fn changes_stay_in_the_overlay() {
+        let base = Rc::new(MemoryStorage::default());
+        write_save_data(&*base, None, 1, "First", b"first").unwrap();
+        write_save_data(&*base, None, 2, "Second", b"second").unwrap();
+
+        let storage = OverlayStorage::new(base.clone());
+
+        let mut writer = storage.open_write(None, 1, false).unwrap();
+        writer.write_at(0, b"F").unwrap();
+        storage.commit(None, 1, writer).unwrap();
+        storage.remove(None, 2).unwrap();
+        write_save_data(&storage, None, 3, "Third", b"third").unwrap();
+
+        assert_eq!(
+            saves(&storage),
+            vec![save(1, "First", b"First"), save(3, "Third", b"third")]
+        );
+        assert!(storage.open_read(None, 2).is_err());
+        assert!(storage.remove(None, 2).is_err());
+        assert_eq!(
+            saves(&*base),
+            vec![save(1, "First", b"first"), save(2, "Second", b"second")]
+        );
+    }
+
+    #[test]
+    fn removed_saves_can_be_written_again() {
+        let base = Rc::new(MemoryStorage::default());
+        write_save_data(&*base, None, 1, "Old", b"old").unwrap();
+
+        let storage = OverlayStorage::new(base);
+        storage.remove(None, 1).unwrap();
+        write_save_data(&storage, None, 1, "New", b"new").unwrap();
+
+        assert_eq!(saves(&storage), vec![save(1, "New", b"new")]);
+
+        storage.remove(None, 1).unwrap();
+
+        assert!(saves(&storage).is_empty());
+    }
+
+    #[test]
+    fn renames_keep_the_snapshot_data() {
+        let base = Rc::new(MemoryStorage::default());
+        write_save_data(&*base, Some(200), 1, "DLC", b"dlc").unwrap();
+
+        let storage = OverlayStorage::new(base.clone());
+        storage.set_name(Some(200), 1, "Renamed").unwrap();
+
+        assert_eq!(
+            storage.stat(Some(200), 1).unwrap(),
+            Some(SaveStat {
+                id: 1,
+                name: "Renamed".to_owned(),
+                size: 3
+            })
+        );
+        assert_eq!(base.stat(Some(200), 1).unwrap().unwrap().name, "DLC");
+    }
+}
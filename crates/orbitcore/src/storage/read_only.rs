@@ -0,0 +1,101 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::rc::Rc;
+
+use anyhow::{Error, Result};
+use derive_new::new;
+
+use super::{SaveRead, SaveStat, SaveStorage, SaveWrite};
+
+/// Serves the saves of another storage and refuses every change, including
+/// changes pulled from elsewhere.
+#[derive(Debug, new)]
+pub struct ReadOnlyStorage {
+    inner: Rc<dyn SaveStorage>,
+}
+
+fn read_only() -> Error {
+    Error::new(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "Saves are read-only",
+    ))
+}
+
+impl SaveStorage for ReadOnlyStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        self.inner.list(product)
+    }
+
+    fn stat(&self, product: Option<u32>, id: u32) -> Result<Option<SaveStat>> {
+        self.inner.stat(product, id)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        self.inner.open_read(product, id)
+    }
+
+    fn open_write(
+        &self,
+        _product: Option<u32>,
+        _id: u32,
+        _truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        Err(read_only())
+    }
+
+    fn commit(&self, _product: Option<u32>, _id: u32, _save: Box<dyn SaveWrite>) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn remove(&self, _product: Option<u32>, _id: u32) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn set_name(&self, _product: Option<u32>, _id: u32, _name: &str) -> Result<()> {
+        Err(read_only())
+    }
+
+    fn is_locked(&self, _product: Option<u32>, _id: u32) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pull(&self, _product: Option<u32>, _in_use: &BTreeSet<u32>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn reads_through_and_refuses_changes() {
+        let inner = Rc::new(MemoryStorage::default());
+        let mut save = inner.open_write(None, 1, true).unwrap();
+        save.write_at(0, b"data").unwrap();
+        inner.commit(None, 1, save).unwrap();
+        inner.set_name(None, 1, "Save").unwrap();
+
+        let storage = ReadOnlyStorage::new(inner);
+        let mut buffer = [0u8; 4];
+
+        assert_eq!(storage.list(None).unwrap().len(), 1);
+        assert_eq!(
+            storage
+                .open_read(None, 1)
+                .unwrap()
+                .read_at(0, &mut buffer)
+                .unwrap(),
+            4
+        );
+        assert!(storage.open_write(None, 1, true).is_err());
+        assert!(storage.remove(None, 1).is_err());
+        assert!(storage.set_name(None, 1, "Renamed").is_err());
+        assert!(storage.is_locked(None, 2).unwrap());
+    }
+}
@@ -0,0 +1,532 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::{fs, iter};
+
+use anyhow::Result;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, MAIN_DB};
+
+use super::memory::{write_data_at, MemoryRead};
+use super::{copy_saves, not_found, FsStorage, SaveRead, SaveStat, SaveStorage, SaveWrite};
+use crate::consts::SAVES_DATABASE_NAME;
+use crate::helpers::save::get_product_ids;
+use crate::helpers::time::get_timestamp;
+
+/// `product` of the configured product's saves.
+const OWN_PRODUCT: i64 = -1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS saves (
+    product INTEGER NOT NULL,
+    id INTEGER NOT NULL,
+    name TEXT,
+    data BLOB NOT NULL DEFAULT x'',
+    modified INTEGER NOT NULL DEFAULT 0,
+    locked INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (product, id)
+)";
+
+/// Adds `locked` to databases from before it was in `SCHEMA`.
+const ADD_LOCKED: &str = "ALTER TABLE saves ADD COLUMN locked INTEGER NOT NULL DEFAULT 0";
+
+const UPSERT_DATA: &str = "
+INSERT INTO saves (product, id, data, modified) VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT (product, id) DO UPDATE SET data = excluded.data, modified = excluded.modified";
+
+const UPSERT_NAME: &str = "
+INSERT INTO saves (product, id, name, modified) VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT (product, id) DO UPDATE SET name = excluded.name, modified = excluded.modified";
+
+type Key = (i64, u32);
+
+/// Names of saves with an open writer, committed together with its data.
+type PendingNames = Rc<RefCell<HashMap<Key, Option<String>>>>;
+
+fn has_column(connection: &Connection, name: &str) -> Result<bool> {
+    let mut statement = connection.prepare("SELECT name FROM pragma_table_info('saves')")?;
+
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(names.iter().any(|column| column == name))
+}
+
+#[inline]
+fn get_key(product: Option<u32>, id: u32) -> Key {
+    (product.map_or(OWN_PRODUCT, i64::from), id)
+}
+
+/// Saves, names and metadata in one `Saves.db` under the saves path. A name
+/// set while the save has an open writer is stored in the same transaction as
+/// the written data, so a save is either complete or not there at all.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Rc<Connection>,
+    pending_names: PendingNames,
+    /// Whether the database has `locked`, which read-only snapshots of older
+    /// databases do not.
+    has_locked: bool,
+}
+
+impl SqliteStorage {
+    pub fn open(saves_path: &Path) -> Result<Self> {
+        if !saves_path.exists() {
+            fs::create_dir_all(saves_path)?;
+        }
+
+        let connection = Connection::open(saves_path.join(SAVES_DATABASE_NAME))?;
+
+        Self::with_connection(connection)
+    }
+
+    /// Opens `Saves.db` read-only, or an empty database if there is none, so
+    /// nothing is created or changed under the saves path.
+    pub fn open_snapshot(saves_path: &Path) -> Result<Self> {
+        let path = saves_path.join(SAVES_DATABASE_NAME);
+
+        if !path.exists() {
+            return Self::open_in_memory();
+        }
+
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        Self::with_connection(connection)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+
+        let mut has_locked = has_column(&connection, "locked")?;
+
+        if !has_locked && !connection.is_readonly(MAIN_DB)? {
+            connection.execute_batch(ADD_LOCKED)?;
+            has_locked = true;
+        }
+
+        Ok(Self {
+            connection: Rc::new(connection),
+            pending_names: Default::default(),
+            has_locked,
+        })
+    }
+
+    /// Locks or unlocks a save, refusing writers and removal while it is
+    /// locked.
+    pub fn set_locked(&self, product: Option<u32>, id: u32, locked: bool) -> Result<()> {
+        let (product, id) = get_key(product, id);
+
+        let updated = self.connection.execute(
+            "UPDATE saves SET locked = ?3 WHERE product = ?1 AND id = ?2",
+            params![product, id, locked],
+        )?;
+
+        if updated == 0 {
+            return Err(not_found(id));
+        }
+
+        Ok(())
+    }
+
+    /// Copies the saves of a folder with `<id>.save` files and a
+    /// `Manifest.toml` into the database, returning how many were copied.
+    pub fn import_folder(&self, saves_path: &Path) -> Result<usize> {
+        let folder = FsStorage::new(saves_path.to_owned());
+        let mut count = 0;
+
+        for product in iter::once(None).chain(get_product_ids(saves_path)?.into_iter().map(Some)) {
+            count += copy_saves(&folder, self, product)?;
+
+            for save in folder.list(product)? {
+                self.set_locked(product, save.id, folder.is_locked(product, save.id)?)?;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Copies the saves back to the folder layout, returning how many were
+    /// copied.
+    pub fn export_folder(&self, saves_path: &Path) -> Result<usize> {
+        let folder = FsStorage::new(saves_path.to_owned());
+        let mut count = 0;
+
+        for product in iter::once(None).chain(self.get_product_ids()?.into_iter().map(Some)) {
+            count += copy_saves(self, &folder, product)?;
+
+            for save in self.list(product)? {
+                folder.set_locked(product, save.id, self.is_locked(product, save.id)?)?;
+            }
+        }
+
+        folder.flush()?;
+
+        Ok(count)
+    }
+
+    fn get_product_ids(&self) -> Result<Vec<u32>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT DISTINCT product FROM saves WHERE product >= 0 ORDER BY product")?;
+
+        let product_ids = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(product_ids)
+    }
+
+    fn get_data(&self, key: Key) -> Result<Option<Vec<u8>>> {
+        let data = self
+            .connection
+            .query_row(
+                "SELECT data FROM saves WHERE product = ?1 AND id = ?2",
+                params![key.0, key.1],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(data)
+    }
+}
+
+#[derive(Debug)]
+struct SqliteWrite {
+    connection: Rc<Connection>,
+    pending_names: PendingNames,
+    key: Key,
+    data: Vec<u8>,
+}
+
+impl SaveWrite for SqliteWrite {
+    fn write_at(&mut self, offset: u64, buffer: &[u8]) -> Result<()> {
+        write_data_at(&mut self.data, offset, buffer);
+
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let (product, id) = self.key;
+        let name = self.pending_names.borrow_mut().remove(&self.key).flatten();
+        let timestamp = get_timestamp() as i64;
+
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute(UPSERT_DATA, params![product, id, self.data, timestamp])?;
+
+        if let Some(name) = name {
+            transaction.execute(UPSERT_NAME, params![product, id, name, timestamp])?;
+        }
+
+        transaction.commit()?;
+
+        Ok(())
+    }
+}
+
+impl Drop for SqliteWrite {
+    fn drop(&mut self) {
+        self.pending_names.borrow_mut().remove(&self.key);
+    }
+}
+
+impl SaveStorage for SqliteStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        let (product, _) = get_key(product, 0);
+
+        let mut statement = self.connection.prepare(
+            "SELECT id, name, length(data) FROM saves
+             WHERE product = ?1 AND name IS NOT NULL ORDER BY id",
+        )?;
+
+        let saves = statement
+            .query_map(params![product], |row| {
+                Ok(SaveStat {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    size: row.get(2)?,
+                })
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+
+        Ok(saves)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        let data = self
+            .get_data(get_key(product, id))?
+            .ok_or_else(|| not_found(id))?;
+
+        Ok(Box::new(MemoryRead::new(Rc::new(data))))
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        let key = get_key(product, id);
+
+        let data = if truncate {
+            Vec::new()
+        } else {
+            self.get_data(key)?.unwrap_or_default()
+        };
+
+        self.pending_names.borrow_mut().insert(key, None);
+
+        Ok(Box::new(SqliteWrite {
+            connection: self.connection.clone(),
+            pending_names: self.pending_names.clone(),
+            key,
+            data,
+        }))
+    }
+
+    fn commit(&self, _product: Option<u32>, _id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        save.finish()
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        let (product, id) = get_key(product, id);
+
+        let removed = self.connection.execute(
+            "DELETE FROM saves WHERE product = ?1 AND id = ?2",
+            params![product, id],
+        )?;
+
+        if removed == 0 {
+            return Err(not_found(id));
+        }
+
+        Ok(())
+    }
+
+    fn is_locked(&self, product: Option<u32>, id: u32) -> Result<bool> {
+        if !self.has_locked {
+            return Ok(false);
+        }
+
+        let (product, id) = get_key(product, id);
+
+        let locked = self
+            .connection
+            .query_row(
+                "SELECT locked FROM saves WHERE product = ?1 AND id = ?2",
+                params![product, id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(locked.unwrap_or(false))
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        let key = get_key(product, id);
+
+        if let Some(pending_name) = self.pending_names.borrow_mut().get_mut(&key) {
+            *pending_name = Some(name.to_owned());
+
+            return Ok(());
+        }
+
+        self.connection.execute(
+            UPSERT_NAME,
+            params![key.0, key.1, name, get_timestamp() as i64],
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::consts::SAVES_MANIFEST_NAME;
+
+    fn write(storage: &dyn SaveStorage, product: Option<u32>, id: u32, data: &[u8], name: &str) {
+        let mut save = storage.open_write(product, id, true).unwrap();
+        save.write_at(0, data).unwrap();
+        storage.set_name(product, id, name).unwrap();
+        storage.commit(product, id, save).unwrap();
+    }
+
+    fn read(storage: &dyn SaveStorage, product: Option<u32>, id: u32) -> Vec<u8> {
+        let mut buffer = vec![0u8; 64];
+        let size = storage
+            .open_read(product, id)
+            .unwrap()
+            .read_at(0, &mut buffer)
+            .unwrap();
+
+        buffer.truncate(size);
+        buffer
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = tempdir().unwrap();
+        let storage = SqliteStorage::open(dir.path()).unwrap();
+
+        write(&storage, None, 1, b"game", "Game");
+        write(&storage, Some(200), 1, b"dlc", "DLC");
+
+        assert!(dir.path().join(SAVES_DATABASE_NAME).exists());
+        assert_eq!(read(&storage, None, 1), b"game");
+        assert_eq!(read(&storage, Some(200), 1), b"dlc");
+        assert_eq!(
+            storage.list(Some(200)).unwrap(),
+            vec![SaveStat {
+                id: 1,
+                name: "DLC".to_owned(),
+                size: 3
+            }]
+        );
+
+        storage.remove(None, 1).unwrap();
+
+        assert!(storage.list(None).unwrap().is_empty());
+        assert!(storage.remove(None, 1).is_err());
+        assert!(storage.open_read(None, 1).is_err());
+    }
+
+    #[test]
+    fn name_is_committed_with_the_data() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+
+        let mut save = storage.open_write(None, 1, true).unwrap();
+        save.write_at(0, b"data").unwrap();
+        storage.set_name(None, 1, "Save").unwrap();
+
+        assert!(storage.list(None).unwrap().is_empty());
+
+        drop(save);
+
+        assert!(storage.list(None).unwrap().is_empty());
+
+        let mut save = storage.open_write(None, 1, true).unwrap();
+        save.write_at(0, b"data").unwrap();
+        storage.set_name(None, 1, "Save").unwrap();
+        storage.commit(None, 1, save).unwrap();
+
+        assert_eq!(storage.list(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rewrite_without_truncate_keeps_the_rest() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        write(&storage, None, 1, b"hello", "Save");
+
+        let mut save = storage.open_write(None, 1, false).unwrap();
+        save.write_at(0, b"J").unwrap();
+        storage.commit(None, 1, save).unwrap();
+
+        assert_eq!(read(&storage, None, 1), b"Jello");
+        assert_eq!(storage.list(None).unwrap()[0].name, "Save");
+    }
+
+    #[test]
+    fn snapshots_are_only_read() {
+        let dir = tempdir().unwrap();
+        let saves_path = dir.path().join("Saves");
+        let empty = SqliteStorage::open_snapshot(&saves_path).unwrap();
+
+        assert!(empty.list(None).unwrap().is_empty());
+        assert!(!saves_path.exists());
+
+        let storage = SqliteStorage::open(&saves_path).unwrap();
+        write(&storage, None, 1, b"game", "Game");
+
+        let snapshot = SqliteStorage::open_snapshot(&saves_path).unwrap();
+        let mut save = snapshot.open_write(None, 2, true).unwrap();
+        save.write_at(0, b"new").unwrap();
+
+        assert_eq!(read(&snapshot, None, 1), b"game");
+        assert!(snapshot.commit(None, 2, save).is_err());
+        assert_eq!(storage.list(None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn import_and_export_folder() {
+        let dir = tempdir().unwrap();
+        let folder = FsStorage::new(dir.path().join("Saves"));
+        write(&folder, None, 1, b"game", "Game");
+        write(&folder, Some(200), 2, b"dlc", "DLC");
+        folder.set_locked(Some(200), 2, true).unwrap();
+        folder.flush().unwrap();
+
+        let storage = SqliteStorage::open_in_memory().unwrap();
+
+        assert_eq!(storage.import_folder(&folder.saves_path).unwrap(), 2);
+        assert_eq!(read(&storage, Some(200), 2), b"dlc");
+        assert!(!storage.is_locked(None, 1).unwrap());
+        assert!(storage.is_locked(Some(200), 2).unwrap());
+
+        let export_path = dir.path().join("Export");
+
+        assert_eq!(storage.export_folder(&export_path).unwrap(), 2);
+        assert!(export_path.join(SAVES_MANIFEST_NAME).exists());
+
+        let exported = FsStorage::new(export_path);
+
+        assert_eq!(exported.list(None).unwrap(), folder.list(None).unwrap());
+        assert_eq!(read(&exported, Some(200), 2), b"dlc");
+        assert!(!exported.is_locked(None, 1).unwrap());
+        assert!(exported.is_locked(Some(200), 2).unwrap());
+    }
+
+    #[test]
+    fn locked_flag_is_stored() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        write(&storage, None, 1, b"game", "Game");
+
+        assert!(!storage.is_locked(None, 1).unwrap());
+        assert!(!storage.is_locked(None, 2).unwrap());
+        assert!(storage.set_locked(None, 2, true).is_err());
+
+        storage.set_locked(None, 1, true).unwrap();
+        write(&storage, None, 1, b"again", "Game");
+
+        assert!(storage.is_locked(None, 1).unwrap());
+        assert!(!storage.is_locked(Some(200), 1).unwrap());
+    }
+
+    #[test]
+    fn older_databases_get_the_locked_flag() {
+        let dir = tempdir().unwrap();
+        let connection = Connection::open(dir.path().join(SAVES_DATABASE_NAME)).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE saves (
+                    product INTEGER NOT NULL,
+                    id INTEGER NOT NULL,
+                    name TEXT,
+                    data BLOB NOT NULL DEFAULT x'',
+                    modified INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (product, id)
+                );
+                INSERT INTO saves (product, id, name, data) VALUES (-1, 1, 'Game', x'00')",
+            )
+            .unwrap();
+        drop(connection);
+
+        let snapshot = SqliteStorage::open_snapshot(dir.path()).unwrap();
+
+        assert!(!snapshot.is_locked(None, 1).unwrap());
+
+        let storage = SqliteStorage::open(dir.path()).unwrap();
+        storage.set_locked(None, 1, true).unwrap();
+
+        assert!(storage.is_locked(None, 1).unwrap());
+    }
+}
@@ -0,0 +1,909 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fmt, fs};
+
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn};
+
+use super::{read_save_data, write_save_data, SaveRead, SaveStat, SaveStorage, SaveWrite};
+use crate::consts::{SAVE_FILE_EXTENSION, SYNC_INDEX_NAME, SYNC_RETRIES, SYNC_STATE_NAME};
+use crate::helpers::manifest::{lock_saves, replace_file};
+use crate::helpers::time::get_timestamp;
+use crate::models::config::{CloudSync, Conflict};
+use crate::models::sync::{Index, RemoteSave, SyncState, SyncedSave, Version};
+
+pub mod remote;
+
+pub use self::remote::{HttpRemote, MemoryRemote, Remote};
+
+/// Uploads saves to a server after they are committed or removed, and pulls
+/// newer revisions before they are listed. Saves that could not be uploaded
+/// are retried on the next pull. The index of a product is only replaced at
+/// the version it was read at, and read again when another machine changed it.
+#[derive(Debug)]
+pub struct SyncStorage {
+    inner: Rc<dyn SaveStorage>,
+    remote: Box<dyn Remote>,
+    product_id: u32,
+    conflict: Conflict,
+    saves_path: PathBuf,
+    state: RefCell<SyncState>,
+}
+
+/// Another machine changed the index since it was read.
+#[derive(Debug)]
+struct IndexChanged;
+
+impl fmt::Display for IndexChanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("The index changed on the server")
+    }
+}
+
+impl std::error::Error for IndexChanged {}
+
+/// Every revision of a save gets its own file, so a machine that loses the
+/// race for the index never replaces the data of the one that won.
+#[inline]
+fn get_remote_save_path(product_id: u32, id: u32, revision: u64) -> String {
+    format!("{}/{}.{}.{}", product_id, id, revision, SAVE_FILE_EXTENSION)
+}
+
+#[inline]
+fn get_state_path(saves_path: &Path) -> PathBuf {
+    saves_path.join(SYNC_STATE_NAME)
+}
+
+#[inline]
+fn get_remote_index_path(product_id: u32) -> String {
+    format!("{}/{}", product_id, SYNC_INDEX_NAME)
+}
+
+impl SyncStorage {
+    pub fn open(
+        inner: Rc<dyn SaveStorage>,
+        sync: &CloudSync,
+        product_id: u32,
+        saves_path: &Path,
+    ) -> Self {
+        let remote = Box::new(HttpRemote::new(sync));
+
+        Self::new(inner, remote, product_id, sync.conflict, saves_path)
+    }
+
+    /// Keeps the revisions of the saves in `Sync.toml` under `saves_path`.
+    pub fn new(
+        inner: Rc<dyn SaveStorage>,
+        remote: Box<dyn Remote>,
+        product_id: u32,
+        conflict: Conflict,
+        saves_path: &Path,
+    ) -> Self {
+        let state_path = get_state_path(saves_path);
+
+        let state = match fs::read_to_string(&state_path) {
+            Ok(state) => toml::from_str(&state).unwrap_or_else(|err| {
+                warn!("{}: {}", state_path.display(), err);
+                SyncState::default()
+            }),
+            Err(_) => SyncState::default(),
+        };
+
+        Self {
+            inner,
+            remote,
+            product_id,
+            conflict,
+            saves_path: saves_path.to_owned(),
+            state: RefCell::new(state),
+        }
+    }
+
+    #[inline]
+    fn get_product_id(&self, product: Option<u32>) -> u32 {
+        product.unwrap_or(self.product_id)
+    }
+
+    fn get_synced(&self, product_id: u32, id: u32) -> Option<SyncedSave> {
+        self.state
+            .borrow()
+            .saves
+            .iter()
+            .find(|save| save.product_id == product_id && save.id == id)
+            .cloned()
+    }
+
+    fn set_synced(&self, synced: SyncedSave) -> Result<()> {
+        {
+            let mut state = self.state.borrow_mut();
+            let saves = &mut state.saves;
+
+            match saves
+                .iter_mut()
+                .find(|save| save.product_id == synced.product_id && save.id == synced.id)
+            {
+                Some(save) => *save = synced,
+                None => saves.push(synced),
+            }
+        }
+
+        self.write_state()
+    }
+
+    /// Replaces `Sync.toml` through a temporary file while holding the lock
+    /// of the saves folder.
+    fn write_state(&self) -> Result<()> {
+        let state = toml::to_string(&*self.state.borrow())?;
+        let _lock = lock_saves(&self.saves_path)?;
+
+        replace_file(&get_state_path(&self.saves_path), state.as_bytes())
+    }
+
+    fn read_index(&self, product_id: u32) -> Result<Index> {
+        let (index, version) = self
+            .remote
+            .get_versioned(&get_remote_index_path(product_id))?;
+
+        let index = match index {
+            Some(index) => toml::from_str(std::str::from_utf8(&index)?)?,
+            None => Index::default(),
+        };
+
+        Ok(Index { version, ..index })
+    }
+
+    /// Fails with `IndexChanged` if the index is not at `index.version` anymore.
+    fn write_index(&self, product_id: u32, index: &mut Index) -> Result<()> {
+        let data = toml::to_string(index)?;
+        let version = self.remote.put_versioned(
+            &get_remote_index_path(product_id),
+            data.as_bytes(),
+            &index.version,
+        )?;
+
+        index.version = version.ok_or(IndexChanged)?;
+
+        Ok(())
+    }
+
+    /// Records a local change and uploads it, logging failed uploads.
+    fn push(&self, product: Option<u32>, id: u32) -> Result<()> {
+        let product_id = self.get_product_id(product);
+        let synced = self.get_synced(product_id, id).unwrap_or(SyncedSave {
+            product_id,
+            id,
+            ..Default::default()
+        });
+
+        self.set_synced(SyncedSave {
+            modified: get_timestamp(),
+            changed: true,
+            ..synced
+        })?;
+
+        let result = self.sync_save_retrying(product, &mut Index::default(), id);
+
+        if let Err(err) = result {
+            warn!(
+                "Savegame {} of {} is not synced yet: {}",
+                id, product_id, err
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Syncs one save, reading the index again when it is stale or another
+    /// machine changed it in between.
+    fn sync_save_retrying(&self, product: Option<u32>, index: &mut Index, id: u32) -> Result<()> {
+        let product_id = self.get_product_id(product);
+
+        for _ in 0..SYNC_RETRIES {
+            if index.version == Version::Stale {
+                *index = self.read_index(product_id)?;
+            }
+
+            match self.sync_save(product, index, id) {
+                Err(err) if err.is::<IndexChanged>() => {
+                    debug!(
+                        "The index of {} changed on the server, syncing savegame {} again",
+                        product_id, id
+                    );
+
+                    index.version = Version::Stale;
+                }
+                result => return result,
+            }
+        }
+
+        Err(anyhow!(
+            "The index of {} kept changing on the server",
+            product_id
+        ))
+    }
+
+    /// Brings one save and its server copy to the same revision.
+    fn sync_save(&self, product: Option<u32>, index: &mut Index, id: u32) -> Result<()> {
+        let product_id = self.get_product_id(product);
+        let local = self.inner.stat(product, id)?;
+        let synced = self.get_synced(product_id, id);
+        let remote = index.saves.iter().find(|save| save.id == id).cloned();
+
+        let revision = synced.as_ref().map_or(0, |synced| synced.revision);
+        let changed = synced
+            .as_ref()
+            .map_or(local.is_some(), |synced| synced.changed);
+        let modified = synced.as_ref().map_or(0, |synced| synced.modified);
+
+        match remote {
+            Some(remote) if remote.revision > revision => {
+                if changed {
+                    self.resolve(product, index, local, modified, remote)
+                } else {
+                    self.download(product, &remote)
+                }
+            }
+            _ if changed => self.upload(product, index, id, local, modified),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve(
+        &self,
+        product: Option<u32>,
+        index: &mut Index,
+        local: Option<SaveStat>,
+        modified: u64,
+        remote: RemoteSave,
+    ) -> Result<()> {
+        let product_id = self.get_product_id(product);
+
+        match self.conflict {
+            Conflict::Ask => {
+                warn!(
+                    "Savegame {} of {} changed here and on the server (revision {}), set \
+                     Conflict to KeepBoth or NewestWins in [Orbit.Sync] to resolve it",
+                    remote.id, product_id, remote.revision
+                );
+
+                Ok(())
+            }
+            Conflict::NewestWins if remote.modified > modified => self.download(product, &remote),
+            Conflict::NewestWins => self.upload(product, index, remote.id, local, modified),
+            Conflict::KeepBoth if local.is_some() && !remote.removed => {
+                let copy_id = self.get_free_id(product, index)?;
+                let data = self.download_data(product_id, &remote)?;
+                let name = format!("{} (server)", remote.name);
+
+                info!(
+                    "Savegame {} of {} changed here and on the server, keeping the server's \
+                     copy as {}",
+                    remote.id, product_id, copy_id
+                );
+
+                write_save_data(&*self.inner, product, copy_id, &name, &data)?;
+                self.set_synced(SyncedSave {
+                    product_id,
+                    id: copy_id,
+                    revision: 0,
+                    modified: remote.modified,
+                    changed: true,
+                })?;
+                // The server's copy is kept, so a retry only uploads this one
+                self.set_synced(SyncedSave {
+                    product_id,
+                    id: remote.id,
+                    revision: remote.revision,
+                    modified,
+                    changed: true,
+                })?;
+                self.sync_save(product, index, copy_id)?;
+
+                self.upload(product, index, remote.id, local, modified)
+            }
+            // Only one side still has the save
+            Conflict::KeepBoth if local.is_some() => {
+                self.upload(product, index, remote.id, local, modified)
+            }
+            Conflict::KeepBoth => self.download(product, &remote),
+        }
+    }
+
+    fn get_free_id(&self, product: Option<u32>, index: &Index) -> Result<u32> {
+        let local_ids = self.inner.list(product)?.into_iter().map(|save| save.id);
+        let remote_ids = index.saves.iter().map(|save| save.id);
+
+        local_ids
+            .chain(remote_ids)
+            .max()
+            .map_or(Some(0), |id| id.checked_add(1))
+            .ok_or_else(|| anyhow!("No savegame id left"))
+    }
+
+    fn download_data(&self, product_id: u32, remote: &RemoteSave) -> Result<Vec<u8>> {
+        self.remote
+            .get(&get_remote_save_path(
+                product_id,
+                remote.id,
+                remote.revision,
+            ))?
+            .ok_or_else(|| {
+                anyhow!(
+                    "Revision {} of savegame {} of {} is missing on the server",
+                    remote.revision,
+                    remote.id,
+                    product_id
+                )
+            })
+    }
+
+    /// Puts the data under the first free revision from `revision` on,
+    /// returning that revision.
+    fn upload_data(&self, product_id: u32, id: u32, revision: u64, data: &[u8]) -> Result<u64> {
+        for revision in revision..revision + u64::from(SYNC_RETRIES) {
+            let path = get_remote_save_path(product_id, id, revision);
+
+            if self
+                .remote
+                .put_versioned(&path, data, &Version::Missing)?
+                .is_some()
+            {
+                return Ok(revision);
+            }
+        }
+
+        Err(IndexChanged.into())
+    }
+
+    /// Removes the data of a revision nobody needs anymore.
+    fn delete_data(&self, product_id: u32, id: u32, revision: u64) {
+        let path = get_remote_save_path(product_id, id, revision);
+
+        if let Err(err) = self.remote.delete(&path) {
+            warn!("Could not remove {} from the server: {}", path, err);
+        }
+    }
+
+    fn download(&self, product: Option<u32>, remote: &RemoteSave) -> Result<()> {
+        let product_id = self.get_product_id(product);
+
+        if remote.removed {
+            if self.inner.stat(product, remote.id)?.is_some() {
+                self.inner.remove(product, remote.id)?;
+            }
+        } else {
+            let data = self.download_data(product_id, remote)?;
+            write_save_data(&*self.inner, product, remote.id, &remote.name, &data)?;
+        }
+
+        self.set_synced(SyncedSave {
+            product_id,
+            id: remote.id,
+            revision: remote.revision,
+            modified: remote.modified,
+            changed: false,
+        })
+    }
+
+    fn upload(
+        &self,
+        product: Option<u32>,
+        index: &mut Index,
+        id: u32,
+        local: Option<SaveStat>,
+        modified: u64,
+    ) -> Result<()> {
+        let product_id = self.get_product_id(product);
+        let synced_revision = self
+            .get_synced(product_id, id)
+            .map_or(0, |synced| synced.revision);
+        let position = index.saves.iter().position(|save| save.id == id);
+        let remote = position.map(|position| index.saves[position].clone());
+        let revision = synced_revision.max(remote.as_ref().map_or(0, |save| save.revision)) + 1;
+
+        let save = match (local, &remote) {
+            (Some(local), _) => {
+                let data = read_save_data(&*self.inner, product, &local)?;
+                let revision = self.upload_data(product_id, id, revision, &data)?;
+
+                RemoteSave {
+                    id,
+                    name: local.name,
+                    revision,
+                    modified,
+                    removed: false,
+                }
+            }
+            (None, Some(remote)) => RemoteSave {
+                revision,
+                modified,
+                removed: true,
+                ..remote.clone()
+            },
+            // Removed before it was ever uploaded
+            (None, None) => {
+                return self.set_synced(SyncedSave {
+                    product_id,
+                    id,
+                    revision: synced_revision,
+                    modified,
+                    changed: false,
+                });
+            }
+        };
+
+        let revision = save.revision;
+        let uploaded = !save.removed;
+
+        match position {
+            Some(position) => index.saves[position] = save,
+            None => index.saves.push(save),
+        }
+
+        if let Err(err) = self.write_index(product_id, index) {
+            if uploaded {
+                self.delete_data(product_id, id, revision);
+            }
+
+            return Err(err);
+        }
+
+        if let Some(remote) = remote.filter(|remote| !remote.removed) {
+            self.delete_data(product_id, id, remote.revision);
+        }
+
+        self.set_synced(SyncedSave {
+            product_id,
+            id,
+            revision,
+            modified,
+            changed: false,
+        })
+    }
+}
+
+impl SaveStorage for SyncStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        self.inner.list(product)
+    }
+
+    fn stat(&self, product: Option<u32>, id: u32) -> Result<Option<SaveStat>> {
+        self.inner.stat(product, id)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        self.inner.open_read(product, id)
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        self.inner.open_write(product, id, truncate)
+    }
+
+    fn commit(&self, product: Option<u32>, id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        self.inner.commit(product, id, save)?;
+        self.push(product, id)
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        self.inner.remove(product, id)?;
+        self.push(product, id)
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        self.inner.set_name(product, id, name)
+    }
+
+    fn is_locked(&self, product: Option<u32>, id: u32) -> Result<bool> {
+        self.inner.is_locked(product, id)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// Saves that are in use or locked are left for a later pull, so an open
+    /// reader or writer never sees its save replaced.
+    fn pull(&self, product: Option<u32>, in_use: &BTreeSet<u32>) -> Result<()> {
+        let product_id = self.get_product_id(product);
+        let mut index = self.read_index(product_id)?;
+
+        let mut ids = BTreeSet::new();
+        ids.extend(index.saves.iter().map(|save| save.id));
+        ids.extend(self.inner.list(product)?.into_iter().map(|save| save.id));
+        ids.extend(
+            self.state
+                .borrow()
+                .saves
+                .iter()
+                .filter(|save| save.product_id == product_id)
+                .map(|save| save.id),
+        );
+
+        for id in ids {
+            if in_use.contains(&id) || self.inner.is_locked(product, id)? {
+                debug!(
+                    "Savegame {} of {} is in use or locked, not syncing it",
+                    id, product_id
+                );
+                continue;
+            }
+
+            self.sync_save_retrying(product, &mut index, id)?;
+        }
+
+        self.inner.pull(product, in_use)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+    use crate::consts::SAVES_MANIFEST_NAME;
+    use crate::storage::{FsStorage, MemoryStorage, ReadOnlyStorage};
+
+    /// A machine with its own saves, syncing with `remote`.
+    struct Machine {
+        dir: TempDir,
+        storage: SyncStorage,
+    }
+
+    impl Machine {
+        fn new(remote: &MemoryRemote, conflict: Conflict) -> Self {
+            Self::with_remote(Box::new(remote.clone()), conflict)
+        }
+
+        fn with_remote(remote: Box<dyn Remote>, conflict: Conflict) -> Self {
+            let dir = tempdir().unwrap();
+            let storage = SyncStorage::new(
+                Rc::new(MemoryStorage::default()),
+                remote,
+                100,
+                conflict,
+                dir.path(),
+            );
+
+            Self { dir, storage }
+        }
+
+        fn save(&self, id: u32, name: &str, data: &[u8]) {
+            write_save_data(&self.storage, None, id, name, data).unwrap();
+        }
+
+        fn saves(&self) -> Vec<(u32, String, Vec<u8>)> {
+            self.storage.pull(None, &BTreeSet::new()).unwrap();
+
+            self.storage
+                .list(None)
+                .unwrap()
+                .into_iter()
+                .map(|save| {
+                    let data = read_save_data(&self.storage, None, &save).unwrap();
+                    (save.id, save.name, data)
+                })
+                .collect()
+        }
+
+        /// Makes the local changes look older than any on the server.
+        fn age_changes(&self) {
+            for save in &mut self.storage.state.borrow_mut().saves {
+                save.modified = 0;
+            }
+        }
+    }
+
+    /// Runs `before_put` before the first write, as if another machine synced
+    /// right after the index was read.
+    struct Interleaved {
+        remote: MemoryRemote,
+        before_put: RefCell<Option<Box<dyn FnOnce()>>>,
+    }
+
+    impl fmt::Debug for Interleaved {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Interleaved")
+                .field("remote", &self.remote)
+                .finish()
+        }
+    }
+
+    impl Remote for Interleaved {
+        fn get_versioned(&self, path: &str) -> Result<(Option<Vec<u8>>, Version)> {
+            self.remote.get_versioned(path)
+        }
+
+        fn put_versioned(
+            &self,
+            path: &str,
+            data: &[u8],
+            version: &Version,
+        ) -> Result<Option<Version>> {
+            if let Some(before_put) = self.before_put.borrow_mut().take() {
+                before_put();
+            }
+
+            self.remote.put_versioned(path, data, version)
+        }
+
+        fn delete(&self, path: &str) -> Result<()> {
+            self.remote.delete(path)
+        }
+    }
+
+    fn save(id: u32, name: &str, data: &[u8]) -> (u32, String, Vec<u8>) {
+        (id, name.to_owned(), data.to_owned())
+    }
+
+    /// Two machines with the same save at revision 1, then changed on both
+    /// while the server was offline.
+    fn conflict(conflict: Conflict) -> (MemoryRemote, Machine, Machine) {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, conflict);
+        let second = Machine::new(&remote, conflict);
+
+        first.save(1, "Save", b"base");
+        second.saves();
+
+        remote.set_offline(true);
+        second.save(1, "Second", b"second");
+        second.age_changes();
+        remote.set_offline(false);
+
+        first.save(1, "First", b"first");
+
+        (remote, first, second)
+    }
+
+    #[test]
+    fn saves_move_between_machines() {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, Conflict::Ask);
+        let second = Machine::new(&remote, Conflict::Ask);
+
+        first.save(1, "Save", b"first");
+
+        assert_eq!(second.saves(), vec![save(1, "Save", b"first")]);
+
+        second.save(1, "Save", b"second");
+
+        assert_eq!(first.saves(), vec![save(1, "Save", b"second")]);
+
+        let index = first.storage.read_index(100).unwrap();
+
+        assert_eq!(index.saves[0].revision, 2);
+        assert!(remote.get("100/1.2.save").unwrap().is_some());
+        assert!(remote.get("100/1.1.save").unwrap().is_none());
+    }
+
+    #[test]
+    fn removals_move_between_machines() {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, Conflict::Ask);
+        let second = Machine::new(&remote, Conflict::Ask);
+
+        first.save(1, "Save", b"first");
+        second.saves();
+        first.storage.remove(None, 1).unwrap();
+
+        assert!(second.saves().is_empty());
+        assert!(remote.get("100/1.1.save").unwrap().is_none());
+    }
+
+    #[test]
+    fn failed_uploads_are_retried_on_pull() {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, Conflict::Ask);
+        let second = Machine::new(&remote, Conflict::Ask);
+
+        remote.set_offline(true);
+        first.save(1, "Save", b"first");
+        remote.set_offline(false);
+
+        assert!(second.saves().is_empty());
+
+        first.saves();
+
+        assert_eq!(second.saves(), vec![save(1, "Save", b"first")]);
+    }
+
+    #[test]
+    fn concurrent_index_changes_are_kept() {
+        let remote = MemoryRemote::default();
+        let second = Machine::new(&remote, Conflict::Ask);
+        let first = Machine::with_remote(
+            Box::new(Interleaved {
+                remote: remote.clone(),
+                before_put: RefCell::new(Some(Box::new(move || {
+                    second.save(2, "Second", b"second")
+                }))),
+            }),
+            Conflict::Ask,
+        );
+
+        first.save(1, "First", b"first");
+
+        let third = Machine::new(&remote, Conflict::Ask);
+
+        assert_eq!(
+            third.saves(),
+            vec![save(1, "First", b"first"), save(2, "Second", b"second")]
+        );
+    }
+
+    #[test]
+    fn racing_uploads_keep_data_and_index_together() {
+        let remote = MemoryRemote::default();
+        let base = Machine::new(&remote, Conflict::Ask);
+        let second = Machine::new(&remote, Conflict::Ask);
+
+        base.save(1, "Base", b"base");
+        second.saves();
+
+        let first = Machine::with_remote(
+            Box::new(Interleaved {
+                remote: remote.clone(),
+                before_put: RefCell::new(Some(Box::new(move || {
+                    second.save(1, "Second", b"second")
+                }))),
+            }),
+            Conflict::Ask,
+        );
+
+        first.saves();
+        first.save(1, "First", b"first");
+
+        let third = Machine::new(&remote, Conflict::Ask);
+
+        assert_eq!(third.saves(), vec![save(1, "Second", b"second")]);
+        assert!(remote.get("100/1.1.save").unwrap().is_none());
+        assert!(remote.get("100/1.3.save").unwrap().is_none());
+        assert_eq!(first.saves(), vec![save(1, "First", b"first")]);
+    }
+
+    #[test]
+    fn stale_index_writes_are_refused() {
+        let remote = MemoryRemote::default();
+        let (_, version) = remote.get_versioned("100/Index.toml").unwrap();
+
+        assert_eq!(version, Version::Missing);
+
+        let written = remote
+            .put_versioned("100/Index.toml", b"", &version)
+            .unwrap();
+
+        assert!(written.is_some());
+        assert!(remote
+            .put_versioned("100/Index.toml", b"", &version)
+            .unwrap()
+            .is_none());
+        assert!(remote
+            .put_versioned("100/Index.toml", b"", &written.unwrap())
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn pull_fails_while_offline() {
+        let remote = MemoryRemote::default();
+        let machine = Machine::new(&remote, Conflict::Ask);
+
+        remote.set_offline(true);
+
+        assert!(machine.storage.pull(None, &BTreeSet::new()).is_err());
+    }
+
+    #[test]
+    fn open_and_locked_saves_are_left_alone() {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, Conflict::Ask);
+        let dir = tempdir().unwrap();
+        let second = SyncStorage::new(
+            Rc::new(FsStorage::new(dir.path().to_owned())),
+            Box::new(remote.clone()),
+            100,
+            Conflict::Ask,
+            dir.path(),
+        );
+
+        first.save(1, "Open", b"one");
+        first.save(2, "Locked", b"two");
+        fs::write(
+            dir.path().join(SAVES_MANIFEST_NAME),
+            "[[Saves]]\nId = 2\nName = \"Locked\"\nLocked = true\n",
+        )
+        .unwrap();
+
+        second.pull(None, &[1].iter().copied().collect()).unwrap();
+
+        assert!(second.list(None).unwrap().is_empty());
+
+        second.pull(None, &BTreeSet::new()).unwrap();
+
+        let ids = second
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|save| save.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(ids, vec![1]);
+        assert!(!dir.path().join("2.save").exists());
+    }
+
+    #[test]
+    fn read_only_sessions_do_not_pull() {
+        let remote = MemoryRemote::default();
+        let first = Machine::new(&remote, Conflict::Ask);
+        let dir = tempdir().unwrap();
+        let inner = Rc::new(MemoryStorage::default());
+        let sync = SyncStorage::new(
+            inner.clone(),
+            Box::new(remote),
+            100,
+            Conflict::Ask,
+            dir.path(),
+        );
+        let storage = ReadOnlyStorage::new(Rc::new(sync));
+
+        first.save(1, "Save", b"data");
+        storage.pull(None, &BTreeSet::new()).unwrap();
+
+        assert!(inner.list(None).unwrap().is_empty());
+        assert!(!dir.path().join(SYNC_STATE_NAME).exists());
+    }
+
+    #[test]
+    fn conflict_newest_wins() {
+        let (_, first, second) = conflict(Conflict::NewestWins);
+
+        assert_eq!(second.saves(), vec![save(1, "First", b"first")]);
+        assert_eq!(first.saves(), vec![save(1, "First", b"first")]);
+    }
+
+    #[test]
+    fn conflict_keeps_both() {
+        let (_, first, second) = conflict(Conflict::KeepBoth);
+
+        let saves = vec![
+            save(1, "Second", b"second"),
+            save(2, "First (server)", b"first"),
+        ];
+
+        assert_eq!(second.saves(), saves);
+        assert_eq!(first.saves(), saves);
+    }
+
+    #[test]
+    fn conflict_is_left_alone_when_asking() {
+        let (_, first, second) = conflict(Conflict::Ask);
+
+        assert_eq!(second.saves(), vec![save(1, "Second", b"second")]);
+        assert_eq!(first.saves(), vec![save(1, "First", b"first")]);
+    }
+
+    #[test]
+    fn revisions_survive_a_restart() {
+        let remote = MemoryRemote::default();
+        let machine = Machine::new(&remote, Conflict::Ask);
+        machine.save(1, "Save", b"data");
+
+        let storage = SyncStorage::new(
+            Rc::new(MemoryStorage::default()),
+            Box::new(remote),
+            100,
+            Conflict::Ask,
+            machine.dir.path(),
+        );
+
+        assert_eq!(storage.get_synced(100, 1).unwrap().revision, 1);
+        assert!(!machine.dir.path().join("Sync.toml.tmp").exists());
+    }
+}
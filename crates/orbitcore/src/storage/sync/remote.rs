@@ -0,0 +1,226 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use ureq::{Agent, AgentBuilder, Request};
+
+use crate::consts::SYNC_TIMEOUT_SECS;
+use crate::models::config::{CloudSync, Protocol};
+use crate::models::sync::Version;
+
+/// Files on the sync server, addressed by paths relative to its folder.
+pub trait Remote: fmt::Debug {
+    /// The file's contents, or `None` if it does not exist.
+    #[inline]
+    fn get(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        self.get_versioned(path).map(|(data, _)| data)
+    }
+
+    /// The file's contents and version.
+    fn get_versioned(&self, path: &str) -> Result<(Option<Vec<u8>>, Version)>;
+
+    #[inline]
+    fn put(&self, path: &str, data: &[u8]) -> Result<()> {
+        self.put_versioned(path, data, &Version::Untagged)
+            .map(|_| ())
+    }
+
+    /// Replaces the file if it is still at `version`. Returns its new version,
+    /// or `None` if it changed since or `version` is stale.
+    fn put_versioned(&self, path: &str, data: &[u8], version: &Version) -> Result<Option<Version>>;
+
+    /// Succeeds if the file does not exist.
+    fn delete(&self, path: &str) -> Result<()>;
+}
+
+/// A plain HTTP server accepting `GET`, `PUT` and `DELETE`, or a WebDAV share.
+/// Writes are checked with `If-Match` when the server sends strong `ETag`s.
+pub struct HttpRemote {
+    agent: Agent,
+    url: String,
+    protocol: Protocol,
+    authorization: Option<String>,
+}
+
+impl fmt::Debug for HttpRemote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpRemote")
+            .field("url", &self.url)
+            .field("protocol", &self.protocol)
+            .finish()
+    }
+}
+
+impl HttpRemote {
+    pub fn new(sync: &CloudSync) -> Self {
+        let agent = AgentBuilder::new()
+            .timeout(Duration::from_secs(SYNC_TIMEOUT_SECS))
+            .build();
+
+        Self {
+            agent,
+            url: sync.url.trim_end_matches('/').to_owned(),
+            protocol: sync.protocol,
+            authorization: sync.authorization.clone(),
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> Request {
+        let request = self
+            .agent
+            .request(method, &format!("{}/{}", self.url, path));
+
+        match &self.authorization {
+            Some(authorization) => request.set("Authorization", authorization),
+            None => request,
+        }
+    }
+
+    /// Creates the WebDAV collection `path` is in.
+    fn create_parent(&self, path: &str) -> Result<()> {
+        let parent = match path.rsplit_once('/') {
+            Some((parent, _)) => parent,
+            None => return Ok(()),
+        };
+
+        let result = self.request("MKCOL", &format!("{}/", parent)).call();
+
+        match result {
+            // Already exists
+            Ok(_) | Err(ureq::Error::Status(405, _)) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The strong `ETag` of a response. Weak ones cannot be used with `If-Match`.
+#[inline]
+fn get_tag(response: &ureq::Response) -> Option<Version> {
+    response
+        .header("ETag")
+        .filter(|tag| tag.starts_with('"'))
+        .map(|tag| Version::Tag(tag.to_owned()))
+}
+
+impl Remote for HttpRemote {
+    fn get_versioned(&self, path: &str) -> Result<(Option<Vec<u8>>, Version)> {
+        let result = self.request("GET", path).call();
+
+        match result {
+            Ok(response) => {
+                let version = get_tag(&response).unwrap_or(Version::Untagged);
+                let mut data = Vec::new();
+                response.into_reader().read_to_end(&mut data)?;
+
+                Ok((Some(data), version))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok((None, Version::Missing)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn put_versioned(&self, path: &str, data: &[u8], version: &Version) -> Result<Option<Version>> {
+        if self.protocol == Protocol::WebDav {
+            self.create_parent(path)?;
+        }
+
+        let request = match version {
+            Version::Stale => return Ok(None),
+            Version::Missing => self.request("PUT", path).set("If-None-Match", "*"),
+            Version::Tag(tag) => self.request("PUT", path).set("If-Match", tag),
+            Version::Untagged => self.request("PUT", path),
+        };
+
+        match request.send_bytes(data) {
+            // Not every server sends the new `ETag`, the file has to be read
+            // again then
+            Ok(response) => Ok(Some(get_tag(&response).unwrap_or(match version {
+                Version::Untagged => Version::Untagged,
+                _ => Version::Stale,
+            }))),
+            Err(ureq::Error::Status(412, _)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        let result = self.request("DELETE", path).call();
+
+        match result {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// The contents and tag of each file.
+type Files = HashMap<String, (Vec<u8>, u64)>;
+
+/// Files kept in memory, shared by every clone. Each write gets a new tag.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryRemote {
+    files: Rc<RefCell<Files>>,
+    last_tag: Rc<Cell<u64>>,
+    offline: Rc<Cell<bool>>,
+}
+
+impl MemoryRemote {
+    /// Fails every request while offline.
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.set(offline);
+    }
+
+    fn check_online(&self) -> Result<()> {
+        if self.offline.get() {
+            return Err(anyhow!("The server is offline"));
+        }
+
+        Ok(())
+    }
+}
+
+impl Remote for MemoryRemote {
+    fn get_versioned(&self, path: &str) -> Result<(Option<Vec<u8>>, Version)> {
+        self.check_online()?;
+
+        Ok(match self.files.borrow().get(path) {
+            Some((data, tag)) => (Some(data.clone()), Version::Tag(tag.to_string())),
+            None => (None, Version::Missing),
+        })
+    }
+
+    fn put_versioned(&self, path: &str, data: &[u8], version: &Version) -> Result<Option<Version>> {
+        self.check_online()?;
+
+        let mut files = self.files.borrow_mut();
+        let current = files.get(path).map(|(_, tag)| tag.to_string());
+
+        let matches = match version {
+            Version::Stale => false,
+            Version::Missing => current.is_none(),
+            Version::Tag(tag) => current.as_ref() == Some(tag),
+            Version::Untagged => true,
+        };
+
+        if !matches {
+            return Ok(None);
+        }
+
+        let tag = self.last_tag.get() + 1;
+        self.last_tag.set(tag);
+        files.insert(path.to_owned(), (data.to_owned(), tag));
+
+        Ok(Some(Version::Tag(tag.to_string())))
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.check_online()?;
+        self.files.borrow_mut().remove(path);
+
+        Ok(())
+    }
+}
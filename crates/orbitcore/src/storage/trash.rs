@@ -0,0 +1,416 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fs, io};
+
+use anyhow::{Error, Result};
+use log::warn;
+
+use super::{
+    not_found, read_save_data, write_save_data, SaveRead, SaveStat, SaveStorage, SaveWrite,
+};
+use crate::consts::{TRASH_FOLDER_NAME, TRASH_INDEX_NAME};
+use crate::helpers::manifest::{lock_saves, replace_file};
+use crate::helpers::save::get_save_path;
+use crate::helpers::time::get_timestamp;
+use crate::models::config::Trash;
+use crate::models::trash::{TrashIndex, TrashedSave};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Moves removed saves into the `Trash` folder with their name and the time
+/// they were removed, so they can be restored. Saves older than the
+/// configured age are purged when the storage is opened and after every
+/// removal.
+#[derive(Debug)]
+pub struct TrashStorage {
+    inner: Rc<dyn SaveStorage>,
+    product_id: u32,
+    saves_path: PathBuf,
+    max_age: u64,
+}
+
+#[inline]
+pub fn get_trash_path(saves_path: &Path) -> PathBuf {
+    saves_path.join(TRASH_FOLDER_NAME)
+}
+
+#[inline]
+pub fn read_trash(saves_path: &Path) -> Result<TrashIndex> {
+    let path = get_trash_path(saves_path).join(TRASH_INDEX_NAME);
+
+    let index = match fs::read_to_string(path) {
+        Ok(index) => toml::from_str(&index)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => TrashIndex::default(),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(index)
+}
+
+/// Reads, changes and writes `Trash.toml` while holding the lock of the
+/// saves folder, like `update_manifest`.
+#[inline]
+fn update_trash<T>(
+    saves_path: &Path,
+    update: impl FnOnce(&mut TrashIndex) -> Result<T>,
+) -> Result<T> {
+    let _lock = lock_saves(saves_path)?;
+
+    let mut index = read_trash(saves_path)?;
+    let result = update(&mut index)?;
+    let path = get_trash_path(saves_path).join(TRASH_INDEX_NAME);
+    replace_file(&path, toml::to_string(&index)?.as_bytes())?;
+
+    Ok(result)
+}
+
+#[inline]
+fn remove_trashed_data(saves_path: &Path, number: u32) -> Result<()> {
+    let path = get_save_path(&get_trash_path(saves_path), number);
+
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Drops the saves removed at least `max_age` seconds before `now`,
+/// returning how many were dropped. A `max_age` of 0 keeps every save.
+pub fn purge_trash(saves_path: &Path, max_age: u64, now: u64) -> Result<usize> {
+    if max_age == 0 {
+        return Ok(0);
+    }
+
+    let is_expired = |save: &TrashedSave| save.removed.saturating_add(max_age) <= now;
+
+    if !read_trash(saves_path)?.saves.iter().any(is_expired) {
+        return Ok(0);
+    }
+
+    update_trash(saves_path, |index| {
+        let (expired, kept) = index
+            .saves
+            .drain(..)
+            .partition::<Vec<_>, _>(|save| is_expired(save));
+
+        index.saves = kept;
+
+        for save in &expired {
+            remove_trashed_data(saves_path, save.number)?;
+        }
+
+        Ok(expired.len())
+    })
+}
+
+/// Puts a trashed save back into `storage`, where `product_id` is the
+/// configured product. A save that took its id in the meantime is kept and
+/// the restored one gets the next free id, which is returned.
+pub fn restore_save(
+    storage: &dyn SaveStorage,
+    product_id: u32,
+    saves_path: &Path,
+    number: u32,
+) -> Result<TrashedSave> {
+    update_trash(saves_path, |index| {
+        let position = index
+            .saves
+            .iter()
+            .position(|save| save.number == number)
+            .ok_or_else(|| {
+                Error::new(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Nothing numbered {} in the trash", number),
+                ))
+            })?;
+
+        let mut save = index.saves[position].clone();
+        let product = Some(save.product_id).filter(|id| *id != product_id);
+        let path = get_save_path(&get_trash_path(saves_path), number);
+        let data = fs::read(path)?;
+
+        if storage.stat(product, save.id)?.is_some() {
+            let last_id = storage
+                .list(product)?
+                .into_iter()
+                .map(|save| save.id)
+                .max()
+                .unwrap_or_default();
+
+            save.id = last_id.checked_add(1).ok_or_else(|| {
+                Error::new(io::Error::new(
+                    io::ErrorKind::StorageFull,
+                    format!("No savegame id left for {}", save.id),
+                ))
+            })?;
+        }
+
+        write_save_data(storage, product, save.id, &save.name, &data)?;
+
+        index.saves.remove(position);
+        remove_trashed_data(saves_path, number)?;
+
+        Ok(save)
+    })
+}
+
+impl TrashStorage {
+    pub fn open(
+        inner: Rc<dyn SaveStorage>,
+        trash: &Trash,
+        product_id: u32,
+        saves_path: &Path,
+    ) -> Self {
+        let storage = Self {
+            inner,
+            product_id,
+            saves_path: saves_path.to_owned(),
+            max_age: trash.max_age_days.saturating_mul(SECONDS_PER_DAY),
+        };
+
+        storage.purge();
+
+        storage
+    }
+
+    fn purge(&self) {
+        if let Err(err) = purge_trash(&self.saves_path, self.max_age, get_timestamp()) {
+            warn!("Could not purge the trash: {}", err);
+        }
+    }
+
+    /// Stores the data of a save in the trash, returning its entry.
+    fn trash(&self, product: Option<u32>, save: &SaveStat) -> Result<TrashedSave> {
+        let data = read_save_data(&*self.inner, product, save)?;
+
+        update_trash(&self.saves_path, |index| {
+            let number = index
+                .saves
+                .iter()
+                .map(|save| save.number)
+                .max()
+                .map_or(1, |number| number + 1);
+
+            let trashed = TrashedSave {
+                number,
+                product_id: product.unwrap_or(self.product_id),
+                id: save.id,
+                name: save.name.clone(),
+                removed: get_timestamp(),
+            };
+
+            let path = get_save_path(&get_trash_path(&self.saves_path), number);
+            replace_file(&path, &data)?;
+
+            index.saves.push(trashed.clone());
+
+            Ok(trashed)
+        })
+    }
+
+    /// Takes a save back out of the trash after its removal failed.
+    fn untrash(&self, trashed: &TrashedSave) -> Result<()> {
+        update_trash(&self.saves_path, |index| {
+            index.saves.retain(|save| save.number != trashed.number);
+
+            remove_trashed_data(&self.saves_path, trashed.number)
+        })
+    }
+}
+
+impl SaveStorage for TrashStorage {
+    fn list(&self, product: Option<u32>) -> Result<Vec<SaveStat>> {
+        self.inner.list(product)
+    }
+
+    fn stat(&self, product: Option<u32>, id: u32) -> Result<Option<SaveStat>> {
+        self.inner.stat(product, id)
+    }
+
+    fn open_read(&self, product: Option<u32>, id: u32) -> Result<Box<dyn SaveRead>> {
+        self.inner.open_read(product, id)
+    }
+
+    fn open_write(
+        &self,
+        product: Option<u32>,
+        id: u32,
+        truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        self.inner.open_write(product, id, truncate)
+    }
+
+    fn commit(&self, product: Option<u32>, id: u32, save: Box<dyn SaveWrite>) -> Result<()> {
+        self.inner.commit(product, id, save)
+    }
+
+    fn remove(&self, product: Option<u32>, id: u32) -> Result<()> {
+        let save = self.inner.stat(product, id)?.ok_or_else(|| not_found(id))?;
+        let trashed = self.trash(product, &save)?;
+
+        let result = self.inner.remove(product, id);
+
+        match result {
+            Ok(()) => {
+                self.purge();
+
+                Ok(())
+            }
+            Err(err) => {
+                if let Err(err) = self.untrash(&trashed) {
+                    warn!("Could not take savegame {} out of the trash: {}", id, err);
+                }
+
+                Err(err)
+            }
+        }
+    }
+
+    fn set_name(&self, product: Option<u32>, id: u32, name: &str) -> Result<()> {
+        self.inner.set_name(product, id, name)
+    }
+
+    fn is_locked(&self, product: Option<u32>, id: u32) -> Result<bool> {
+        self.inner.is_locked(product, id)
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    fn pull(&self, product: Option<u32>, in_use: &BTreeSet<u32>) -> Result<()> {
+        self.inner.pull(product, in_use)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::storage::MemoryStorage;
+
+    fn trash() -> Trash {
+        Trash {
+            enabled: true,
+            max_age_days: 1,
+        }
+    }
+
+    #[test]
+    fn removal_goes_to_the_trash_and_back() {
+        let dir = tempdir().unwrap();
+        let inner = Rc::new(MemoryStorage::default());
+        let storage = TrashStorage::open(inner.clone(), &trash(), 46, dir.path());
+
+        write_save_data(&storage, None, 1, "Chapter 1", b"one").unwrap();
+        write_save_data(&storage, Some(200), 1, "DLC", b"dlc").unwrap();
+        storage.remove(None, 1).unwrap();
+        storage.remove(Some(200), 1).unwrap();
+
+        assert!(inner.list(None).unwrap().is_empty());
+        assert!(storage.remove(None, 1).is_err());
+
+        let index = read_trash(dir.path()).unwrap();
+
+        assert_eq!(index.saves.len(), 2);
+        assert_eq!(index.saves[0].product_id, 46);
+        assert_eq!(index.saves[0].name, "Chapter 1");
+        assert_eq!(index.saves[1].product_id, 200);
+
+        let save = restore_save(&storage, 46, dir.path(), 2).unwrap();
+        assert_eq!(save.id, 1);
+        assert_eq!(
+            read_save_data(
+                &storage,
+                Some(200),
+                &storage.stat(Some(200), 1).unwrap().unwrap()
+            )
+            .unwrap(),
+            b"dlc"
+        );
+
+        assert_eq!(read_trash(dir.path()).unwrap().saves.len(), 1);
+        assert!(restore_save(&storage, 46, dir.path(), 2).is_err());
+    }
+
+    #[test]
+    fn restore_keeps_saves_that_took_the_id() {
+        let dir = tempdir().unwrap();
+        let storage =
+            TrashStorage::open(Rc::new(MemoryStorage::default()), &trash(), 46, dir.path());
+
+        write_save_data(&storage, None, 1, "Old", b"old").unwrap();
+        storage.remove(None, 1).unwrap();
+        write_save_data(&storage, None, 1, "New", b"new").unwrap();
+
+        let save = restore_save(&storage, 46, dir.path(), 1).unwrap();
+
+        assert_eq!(save.id, 2);
+        assert_eq!(
+            storage
+                .list(None)
+                .unwrap()
+                .into_iter()
+                .map(|save| save.name)
+                .collect::<Vec<_>>(),
+            vec!["New", "Old"]
+        );
+    }
+
+    #[test]
+    fn trash_waits_for_the_saves_lock() {
+        let dir = tempdir().unwrap();
+        let inner = Rc::new(MemoryStorage::default());
+        let storage = TrashStorage::open(inner.clone(), &trash(), 46, dir.path());
+        write_save_data(&storage, None, 1, "Save", b"data").unwrap();
+
+        let lock = lock_saves(dir.path()).unwrap();
+        let err = storage.remove(None, 1).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<io::Error>().map(io::Error::kind),
+            Some(io::ErrorKind::ResourceBusy)
+        );
+        assert_eq!(inner.list(None).unwrap().len(), 1);
+
+        drop(lock);
+        storage.remove(None, 1).unwrap();
+
+        let mut files = fs::read_dir(get_trash_path(dir.path()))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+
+        assert_eq!(files, vec!["1.save", TRASH_INDEX_NAME]);
+    }
+
+    #[test]
+    fn old_saves_are_purged() {
+        let dir = tempdir().unwrap();
+        let storage =
+            TrashStorage::open(Rc::new(MemoryStorage::default()), &trash(), 46, dir.path());
+
+        write_save_data(&storage, None, 1, "Save", b"data").unwrap();
+        storage.remove(None, 1).unwrap();
+
+        let removed = read_trash(dir.path()).unwrap().saves[0].removed;
+        let data_path = get_save_path(&get_trash_path(dir.path()), 1);
+
+        assert_eq!(purge_trash(dir.path(), 0, u64::MAX).unwrap(), 0);
+        assert_eq!(
+            purge_trash(dir.path(), SECONDS_PER_DAY, removed + 60).unwrap(),
+            0
+        );
+        assert!(data_path.is_file());
+
+        assert_eq!(
+            purge_trash(dir.path(), SECONDS_PER_DAY, removed + SECONDS_PER_DAY).unwrap(),
+            1
+        );
+        assert!(read_trash(dir.path()).unwrap().saves.is_empty());
+        assert!(!data_path.exists());
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::{anyhow, Error, Result};
+use derive_new::new;
+
+use super::{SaveRead, SaveStat, SaveStorage, SaveWrite};
+
+/// Stands in for a storage that could not be opened. Every call fails with
+/// the reason, so the game never gets to write saves that are not kept.
+#[derive(Debug, new)]
+pub struct UnavailableStorage {
+    reason: String,
+}
+
+impl UnavailableStorage {
+    fn unavailable(&self) -> Error {
+        anyhow!("Saves are unavailable: {}", self.reason)
+    }
+}
+
+impl SaveStorage for UnavailableStorage {
+    fn list(&self, _product: Option<u32>) -> Result<Vec<SaveStat>> {
+        Err(self.unavailable())
+    }
+
+    fn stat(&self, _product: Option<u32>, _id: u32) -> Result<Option<SaveStat>> {
+        Err(self.unavailable())
+    }
+
+    fn open_read(&self, _product: Option<u32>, _id: u32) -> Result<Box<dyn SaveRead>> {
+        Err(self.unavailable())
+    }
+
+    fn open_write(
+        &self,
+        _product: Option<u32>,
+        _id: u32,
+        _truncate: bool,
+    ) -> Result<Box<dyn SaveWrite>> {
+        Err(self.unavailable())
+    }
+
+    fn commit(&self, _product: Option<u32>, _id: u32, _save: Box<dyn SaveWrite>) -> Result<()> {
+        Err(self.unavailable())
+    }
+
+    fn remove(&self, _product: Option<u32>, _id: u32) -> Result<()> {
+        Err(self.unavailable())
+    }
+
+    fn set_name(&self, _product: Option<u32>, _id: u32, _name: &str) -> Result<()> {
+        Err(self.unavailable())
+    }
+
+    fn is_locked(&self, _product: Option<u32>, _id: u32) -> Result<bool> {
+        Err(self.unavailable())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::write_save_data;
+
+    #[test]
+    fn every_call_fails_with_the_reason() {
+        let storage = UnavailableStorage::new("Saves.db is corrupt".to_owned());
+
+        let err = storage.list(None).unwrap_err();
+
+        assert!(err.to_string().ends_with("Saves.db is corrupt"));
+        assert!(storage.open_read(None, 1).is_err());
+        assert!(write_save_data(&storage, None, 1, "Save", b"data").is_err());
+        assert!(storage.remove(None, 1).is_err());
+    }
+}
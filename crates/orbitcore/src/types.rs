@@ -0,0 +1,822 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::process::Child;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Error, Result};
+use derive_new::new;
+use log::{debug, error};
+use ustr::Ustr;
+use widestring::U16CString;
+
+use crate::helpers::process::start_command;
+use crate::installation::Installation;
+use crate::models::config::Quota;
+use crate::quota::{PendingWrite, PendingWrites};
+use crate::storage::{MemoryStorage, SaveRead, SaveStorage, SaveWrite};
+
+#[derive(Debug)]
+pub struct Inner {
+    pub request_id: u32,
+    pub storage: Rc<dyn SaveStorage>,
+    pub product_id: u32,
+    pub product_ids: Vec<u32>,
+    pub cd_keys: HashMap<u32, Vec<Ustr>>,
+    pub savegame_info_list: Vec<Box<SavegameInfo>>,
+    pub quota: Quota,
+    pub installation: Installation,
+    pub savegame_readers: HashMap<HandleKey, Box<SavegameReader>>,
+    pub savegame_writers: HashMap<HandleKey, Box<SavegameWriter>>,
+    pub pending_writes: Rc<PendingWrites>,
+    /// The launcher started for this client, if any.
+    pub launcher: Option<Child>,
+}
+
+/// Identifies an open reader or writer handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, new)]
+pub struct HandleKey {
+    pub save_game_id: u32,
+    pub request_id: u32,
+}
+
+#[derive(Debug)]
+pub struct OrbitClient {
+    inner: Box<Inner>,
+}
+
+impl OrbitClient {
+    pub fn new(storage: Rc<dyn SaveStorage>, product_id: u32, product_ids: Vec<u32>) -> Self {
+        Self {
+            inner: Box::new(Inner {
+                request_id: 0,
+                storage,
+                product_id,
+                product_ids,
+                cd_keys: Default::default(),
+                savegame_info_list: Default::default(),
+                quota: Default::default(),
+                installation: Default::default(),
+                savegame_readers: Default::default(),
+                savegame_writers: Default::default(),
+                pending_writes: Default::default(),
+                launcher: None,
+            }),
+        }
+    }
+
+    /// The configured product, 0 for it, and the listed products are allowed.
+    /// Without a configured product every product is.
+    pub fn is_product_allowed(&self, product_id: u32) -> bool {
+        self.product_id == 0
+            || product_id == 0
+            || product_id == self.product_id
+            || self.product_ids.contains(&product_id)
+    }
+
+    pub fn check_product(&self, product_id: u32) -> Result<()> {
+        if !self.is_product_allowed(product_id) {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Product id {} is not allowed", product_id),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Saves of the configured product (or of every product, when none is
+    /// configured) are stored as `None`, other products under their id.
+    pub fn get_storage_product(&self, product_id: u32) -> Result<Option<u32>> {
+        self.check_product(product_id)?;
+
+        if self.product_id == 0 || product_id == 0 || product_id == self.product_id {
+            return Ok(None);
+        }
+
+        Ok(Some(product_id))
+    }
+
+    pub fn get_next_request_id(&mut self) -> u32 {
+        self.inner.request_id += 1;
+        self.inner.request_id
+    }
+
+    /// CD keys of a product. Without a configured product id, the keys
+    /// configured for it are served to every product.
+    pub fn get_cd_keys(&self, product_id: u32) -> Result<&[Ustr]> {
+        self.check_product(product_id)?;
+
+        let cd_keys = match self.cd_keys.get(&product_id) {
+            Some(cd_keys) => cd_keys,
+            None if self.product_id == 0 => self.cd_keys.get(&0).map_or(&[][..], Vec::as_slice),
+            None => &[],
+        };
+
+        Ok(cd_keys)
+    }
+
+    /// Starts the launcher command, unless the one started before for this
+    /// client is still running.
+    pub fn start_launcher(&mut self, command: &[String]) -> Result<()> {
+        if let Some(launcher) = self.launcher.as_mut() {
+            if launcher.try_wait()?.is_none() {
+                debug!("The launcher is still running");
+                return Ok(());
+            }
+        }
+
+        self.launcher = start_command(command)?;
+
+        Ok(())
+    }
+
+    /// Fetches saves changed elsewhere, such as on a sync server, except the
+    /// ones the game has open.
+    pub fn pull_savegames(&self, product_id: u32) -> Result<()> {
+        let product = self.get_storage_product(product_id)?;
+        self.storage.pull(product, &self.get_open_ids(product))
+    }
+
+    /// Saves of a product with an open reader or writer.
+    fn get_open_ids(&self, product: Option<u32>) -> BTreeSet<u32> {
+        let readers = self
+            .savegame_readers
+            .values()
+            .filter(|reader| !reader.is_closed() && reader.product == product)
+            .map(|reader| reader.id);
+        let writers = self
+            .savegame_writers
+            .values()
+            .filter(|writer| !writer.is_closed() && writer.product == product)
+            .map(|writer| writer.id);
+
+        readers.chain(writers).collect()
+    }
+
+    /// Writes out save names and removals kept in memory by the storage.
+    pub fn flush_savegames(&self) -> Result<()> {
+        self.storage.flush()
+    }
+
+    pub fn get_savegame_list(&self, product_id: u32) -> Result<Vec<Box<SavegameInfo>>> {
+        let product = self.get_storage_product(product_id)?;
+        let saves = self.storage.list(product)?;
+        let mut save_info_list = Vec::new();
+
+        for save in saves {
+            let size = save.size as u32;
+            let u16name = U16CString::from_str(save.name)?;
+
+            save_info_list.push(Box::new(SavegameInfo {
+                id: save.id,
+                size,
+                name: u16name,
+            }));
+        }
+
+        Ok(save_info_list)
+    }
+
+    pub fn get_savegame_writer(
+        &mut self,
+        request_id: u32,
+        product_id: u32,
+        save_game_id: u32,
+        open: bool,
+    ) -> Result<&SavegameWriter> {
+        self.release_closed_handles();
+
+        let product = self.get_storage_product(product_id)?;
+        let key = HandleKey::new(save_game_id, request_id);
+
+        self.check_handle(key)?;
+        self.check_locked(product, save_game_id)?;
+        self.check_lock(product, save_game_id, true)?;
+
+        self.quota.check_writer(
+            self.storage.as_ref(),
+            product,
+            save_game_id,
+            open,
+            &self.pending_writes.get(product),
+        )?;
+
+        let writer = SavegameWriter::new(
+            save_game_id,
+            product,
+            open,
+            self.quota.clone(),
+            self.storage.clone(),
+            self.pending_writes.clone(),
+        );
+        self.pending_writes
+            .insert(product, writer.get_pending_write());
+        let writer = self.savegame_writers.entry(key).or_insert(Box::new(writer));
+
+        Ok(&**writer)
+    }
+
+    pub fn get_savegame_reader(
+        &mut self,
+        request_id: u32,
+        product_id: u32,
+        save_game_id: u32,
+    ) -> Result<&SavegameReader> {
+        self.release_closed_handles();
+
+        let product = self.get_storage_product(product_id)?;
+        let key = HandleKey::new(save_game_id, request_id);
+
+        self.check_handle(key)?;
+        self.check_lock(product, save_game_id, false)?;
+
+        let save = self.storage.open_read(product, save_game_id)?;
+        let reader = SavegameReader::new(save_game_id, product, RefCell::new(Some(save)));
+        let reader = self.savegame_readers.entry(key).or_insert(Box::new(reader));
+
+        Ok(&**reader)
+    }
+
+    /// Frees the readers and writers the game has closed.
+    pub fn release_closed_handles(&mut self) {
+        self.savegame_readers
+            .retain(|_, reader| !reader.is_closed());
+        self.savegame_writers
+            .retain(|_, writer| !writer.is_closed());
+    }
+
+    fn check_handle(&self, key: HandleKey) -> Result<()> {
+        if self.savegame_readers.contains_key(&key) || self.savegame_writers.contains_key(&key) {
+            return Err(anyhow!(
+                "Request {} already has savegame {} open",
+                key.request_id,
+                key.save_game_id
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Locked saves, and every save of a read-only session, are only read.
+    fn check_locked(&self, product: Option<u32>, save_game_id: u32) -> Result<()> {
+        if self.storage.is_locked(product, save_game_id)? {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("Savegame {} is locked", save_game_id),
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Any number of readers, or a single writer, may have a save open.
+    fn check_lock(&self, product: Option<u32>, save_game_id: u32, write: bool) -> Result<()> {
+        let writing = self.savegame_writers.values().any(|writer| {
+            !writer.is_closed() && writer.product == product && writer.id == save_game_id
+        });
+        let reading = self.savegame_readers.values().any(|reader| {
+            !reader.is_closed() && reader.product == product && reader.id == save_game_id
+        });
+
+        if writing || (write && reading) {
+            return Err(Error::new(io::Error::new(
+                io::ErrorKind::ResourceBusy,
+                format!("Savegame {} is in use", save_game_id),
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn remove_savegame(&self, product_id: u32, save_game_id: u32) -> Result<()> {
+        let product = self.get_storage_product(product_id)?;
+
+        self.check_locked(product, save_game_id)?;
+        self.check_lock(product, save_game_id, true)?;
+
+        self.storage.remove(product, save_game_id)
+    }
+}
+
+impl Drop for OrbitClient {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush_savegames() {
+            error!("{}", err);
+        }
+    }
+}
+
+impl Default for OrbitClient {
+    fn default() -> Self {
+        Self::new(Rc::new(MemoryStorage::default()), 0, Vec::new())
+    }
+}
+
+impl Deref for OrbitClient {
+    type Target = Inner;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl DerefMut for OrbitClient {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[derive(Debug, new)]
+pub struct SavegameInfo {
+    pub id: u32,
+    pub size: u32,
+    pub name: U16CString,
+}
+
+#[derive(Debug, new)]
+pub struct SavegameReader {
+    pub id: u32,
+    pub product: Option<u32>,
+    save: RefCell<Option<Box<dyn SaveRead>>>,
+    #[new(default)]
+    closed: Cell<bool>,
+}
+
+impl SavegameReader {
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Releases the save handle.
+    pub fn close(&self) {
+        self.closed.set(true);
+        self.save.borrow_mut().take();
+    }
+
+    /// Fills `buffer` from `offset`, returning how many bytes were read.
+    pub fn read(&self, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+        let mut save = self.save.borrow_mut();
+        let save = save
+            .as_mut()
+            .ok_or_else(|| anyhow!("Savegame {} is closed", self.id))?;
+
+        save.read_at(offset, buffer)
+    }
+}
+
+#[derive(Debug, new)]
+pub struct SavegameWriter {
+    pub id: u32,
+    pub product: Option<u32>,
+    pub truncate: bool,
+    quota: Quota,
+    storage: Rc<dyn SaveStorage>,
+    pending_writes: Rc<PendingWrites>,
+    #[new(default)]
+    save: RefCell<Option<Box<dyn SaveWrite>>>,
+    #[new(default)]
+    cursor: Cell<u64>,
+    #[new(default)]
+    closed: Cell<bool>,
+}
+
+impl SavegameWriter {
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    #[inline]
+    pub fn get_pending_write(&self) -> PendingWrite {
+        PendingWrite {
+            id: self.id,
+            truncate: self.truncate,
+            cursor: self.cursor.get(),
+        }
+    }
+
+    /// Commits the written data, writes out the name and releases the save
+    /// handle.
+    pub fn close(&self) -> Result<()> {
+        self.closed.set(true);
+        self.pending_writes.remove(self.product, self.id);
+
+        if let Some(save) = self.save.borrow_mut().take() {
+            self.storage.commit(self.product, self.id, save)?;
+        }
+
+        self.storage.flush()
+    }
+
+    /// Writes at the cursor, opening the save on the first use.
+    pub fn write(&self, buffer: &[u8]) -> Result<()> {
+        if self.is_closed() {
+            return Err(anyhow!("Savegame {} is closed", self.id));
+        }
+
+        let end = self.cursor.get() + buffer.len() as u64;
+        let write = PendingWrite {
+            cursor: end,
+            ..self.get_pending_write()
+        };
+
+        self.quota.check_write(
+            self.storage.as_ref(),
+            self.product,
+            write,
+            &self.pending_writes.get(self.product),
+        )?;
+
+        self.open()?;
+
+        if let Some(save) = self.save.borrow_mut().as_mut() {
+            save.write_at(self.cursor.get(), buffer)?;
+        }
+
+        self.cursor.set(end);
+        self.pending_writes.insert(self.product, write);
+
+        Ok(())
+    }
+
+    /// Names the save. Storages that commit atomically store the name
+    /// together with data written before the writer is closed; without any,
+    /// only the name changes.
+    pub fn set_name(&self, name: String) -> Result<()> {
+        if self.is_closed() {
+            return Err(anyhow!("Savegame {} is closed", self.id));
+        }
+
+        self.storage.set_name(self.product, self.id, &name)
+    }
+
+    #[inline]
+    fn open(&self) -> Result<()> {
+        let mut save = self.save.borrow_mut();
+
+        if save.is_none() {
+            *save = Some(
+                self.storage
+                    .open_write(self.product, self.id, self.truncate)?,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::consts::SAVES_MANIFEST_NAME;
+    use crate::error::ErrorCode;
+    use crate::storage::{FsStorage, SaveStat};
+
+    fn client(product_id: u32, product_ids: Vec<u32>) -> OrbitClient {
+        OrbitClient::new(Rc::new(MemoryStorage::default()), product_id, product_ids)
+    }
+
+    #[test]
+    fn request_ids_are_sequential() {
+        let mut client = OrbitClient::default();
+
+        assert_eq!(client.get_next_request_id(), 1);
+        assert_eq!(client.get_next_request_id(), 2);
+    }
+
+    #[test]
+    fn write_name_list_read_remove() {
+        let mut client = client(0, Vec::new());
+
+        let writer = client.get_savegame_writer(1, 0, 5, true).unwrap();
+        writer.write(b"savegame").unwrap();
+        writer.set_name("Slot 5".to_owned()).unwrap();
+        writer.close().unwrap();
+
+        let list = client.get_savegame_list(0).unwrap();
+
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].id, 5);
+        assert_eq!(list[0].size, 8);
+        assert_eq!(list[0].name.to_string().unwrap(), "Slot 5");
+
+        let reader = client.get_savegame_reader(2, 0, 5).unwrap();
+        let mut buffer = [0u8; 4];
+
+        assert_eq!(reader.read(4, &mut buffer).unwrap(), 4);
+        assert_eq!(&buffer, b"game");
+
+        reader.close();
+
+        client.remove_savegame(0, 5).unwrap();
+
+        assert!(client.get_savegame_list(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn saves_are_scoped_per_product() {
+        let dir = tempdir().unwrap();
+        let saves_path = dir.path().join("Saves");
+        let storage = Rc::new(FsStorage::new(saves_path.clone()));
+        let mut client = OrbitClient::new(storage, 100, vec![200]);
+
+        let writer = client.get_savegame_writer(1, 100, 1, true).unwrap();
+        writer.write(b"game").unwrap();
+        writer.set_name("Game".to_owned()).unwrap();
+
+        let writer = client.get_savegame_writer(2, 200, 1, true).unwrap();
+        writer.write(b"dlc").unwrap();
+        writer.set_name("DLC".to_owned()).unwrap();
+
+        assert!(saves_path.join("1.save").is_file());
+        assert!(saves_path.join("200").join("1.save").is_file());
+        assert!(client.get_savegame_list(100).unwrap().is_empty());
+
+        for writer in client.savegame_writers.values() {
+            writer.close().unwrap();
+        }
+
+        assert_eq!(client.get_savegame_list(100).unwrap()[0].size, 4);
+        assert_eq!(client.get_savegame_list(200).unwrap()[0].size, 3);
+    }
+
+    #[test]
+    fn locked_saves_are_only_read() {
+        let dir = tempdir().unwrap();
+        let storage = Rc::new(FsStorage::new(dir.path().to_owned()));
+        let mut client = OrbitClient::new(storage, 0, Vec::new());
+
+        let writer = client.get_savegame_writer(1, 0, 1, true).unwrap();
+        writer.write(b"reference").unwrap();
+        writer.set_name("QA".to_owned()).unwrap();
+        writer.close().unwrap();
+
+        let manifest = fs::read_to_string(dir.path().join(SAVES_MANIFEST_NAME)).unwrap();
+        fs::write(
+            dir.path().join(SAVES_MANIFEST_NAME),
+            manifest + "Locked = true\n",
+        )
+        .unwrap();
+
+        let err = client.get_savegame_writer(2, 0, 1, true).unwrap_err();
+
+        assert_eq!(ErrorCode::from(&err), ErrorCode::AccessDenied);
+        assert!(client.remove_savegame(0, 1).is_err());
+        assert!(client.get_savegame_writer(3, 0, 2, true).is_ok());
+
+        let reader = client.get_savegame_reader(4, 0, 1).unwrap();
+        let mut buffer = [0u8; 9];
+
+        assert_eq!(reader.read(0, &mut buffer).unwrap(), 9);
+        assert_eq!(&buffer, b"reference");
+    }
+
+    #[test]
+    fn other_products_are_rejected() {
+        let mut client = client(100, vec![200]);
+
+        assert!(client.is_product_allowed(100));
+        assert!(client.is_product_allowed(200));
+        assert!(!client.is_product_allowed(300));
+        assert!(client.get_savegame_list(300).is_err());
+        assert!(client.get_savegame_writer(1, 300, 1, true).is_err());
+        assert!(client.get_savegame_reader(2, 300, 1).is_err());
+        assert!(client.remove_savegame(300, 1).is_err());
+    }
+
+    #[test]
+    fn handles_live_until_closed() {
+        let mut client = client(0, Vec::new());
+
+        let first = client.get_savegame_writer(1, 0, 1, true).unwrap() as *const SavegameWriter;
+        let second = client.get_savegame_writer(2, 0, 2, true).unwrap() as *const SavegameWriter;
+
+        assert_eq!(client.savegame_writers.len(), 2);
+        assert_eq!(unsafe { (*first).id }, 1);
+        assert_eq!(unsafe { (*second).id }, 2);
+
+        unsafe { (*first).close().unwrap() };
+        client.release_closed_handles();
+
+        assert_eq!(client.savegame_writers.len(), 1);
+        assert!(client.savegame_writers.contains_key(&HandleKey::new(2, 2)));
+    }
+
+    #[test]
+    fn open_ids_per_product() {
+        let mut client = client(100, vec![200]);
+
+        let writer = client.get_savegame_writer(1, 100, 1, true).unwrap();
+        writer.write(b"game").unwrap();
+        writer.set_name("Game".to_owned()).unwrap();
+        writer.close().unwrap();
+
+        client.get_savegame_reader(2, 100, 1).unwrap();
+        client.get_savegame_writer(3, 100, 2, true).unwrap();
+        client.get_savegame_writer(4, 200, 3, true).unwrap();
+
+        assert_eq!(
+            client.get_open_ids(None).into_iter().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            client
+                .get_open_ids(Some(200))
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn writer_appends_chunks() {
+        let mut client = client(0, Vec::new());
+
+        let writer = client.get_savegame_writer(1, 0, 1, true).unwrap();
+        writer.write(b"hello").unwrap();
+        writer.write(b" ").unwrap();
+        writer.write(b"world").unwrap();
+        writer.close().unwrap();
+
+        assert!(writer.write(b"!").is_err());
+
+        let mut buffer = [0u8; 16];
+        let mut save = client.storage.open_read(None, 1).unwrap();
+
+        assert_eq!(save.read_at(0, &mut buffer).unwrap(), 11);
+        assert_eq!(&buffer[..11], b"hello world");
+    }
+
+    #[test]
+    fn closed_writers_cannot_rename() {
+        let mut client = client(0, Vec::new());
+
+        let writer = client.get_savegame_writer(1, 0, 1, true).unwrap();
+        writer.write(b"data").unwrap();
+        writer.set_name("Save".to_owned()).unwrap();
+        writer.close().unwrap();
+
+        assert!(writer.set_name("Renamed".to_owned()).is_err());
+
+        let writer = client.get_savegame_writer(2, 0, 1, true).unwrap();
+        writer.set_name("Renamed".to_owned()).unwrap();
+        writer.close().unwrap();
+
+        assert_eq!(
+            client.storage.stat(None, 1).unwrap(),
+            Some(SaveStat {
+                id: 1,
+                name: "Renamed".to_owned(),
+                size: 4
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(all(unix, not(miri)))]
+    fn launcher_is_started_once_while_running() {
+        let mut client = client(0, Vec::new());
+        let sleep = vec!["sleep".to_owned(), "10".to_owned()];
+
+        client.start_launcher(&sleep).unwrap();
+        let id = client.launcher.as_ref().unwrap().id();
+        client.start_launcher(&sleep).unwrap();
+
+        assert_eq!(client.launcher.as_ref().unwrap().id(), id);
+
+        let launcher = client.launcher.as_mut().unwrap();
+        launcher.kill().unwrap();
+        launcher.wait().unwrap();
+        client.start_launcher(&sleep).unwrap();
+
+        let launcher = client.launcher.as_mut().unwrap();
+
+        assert_ne!(launcher.id(), id);
+
+        launcher.kill().unwrap();
+        launcher.wait().unwrap();
+    }
+
+    #[test]
+    fn writer_respects_quota() {
+        let mut client = client(0, Vec::new());
+        client.quota = Quota {
+            max_save_bytes: Some(8),
+            max_id: Some(9),
+            ..Default::default()
+        };
+
+        assert!(client.get_savegame_writer(1, 0, 10, true).is_err());
+
+        let writer = client.get_savegame_writer(2, 0, 1, true).unwrap();
+
+        assert!(writer.write(b"12345").is_ok());
+        assert!(writer.write(b"6789").is_err());
+        assert!(writer.write(b"678").is_ok());
+    }
+
+    #[test]
+    fn open_writers_count_against_the_quota() {
+        let mut client = client(0, Vec::new());
+        client.quota = Quota {
+            max_slots: Some(3),
+            max_total_bytes: Some(8),
+            ..Default::default()
+        };
+
+        client.get_savegame_writer(1, 0, 1, true).unwrap();
+        client
+            .get_savegame_writer(2, 0, 2, true)
+            .unwrap()
+            .write(b"12345")
+            .unwrap();
+
+        let writer = client.get_savegame_writer(3, 0, 3, true).unwrap();
+
+        assert!(writer.write(b"1234").is_err());
+        assert!(writer.write(b"123").is_ok());
+        assert!(client.get_savegame_writer(4, 0, 4, true).is_err());
+    }
+
+    #[test]
+    fn writers_opened_together_share_the_quota() {
+        let mut client = client(0, Vec::new());
+        client.quota = Quota {
+            max_total_bytes: Some(8),
+            ..Default::default()
+        };
+
+        client.get_savegame_writer(1, 0, 1, true).unwrap();
+        client.get_savegame_writer(2, 0, 2, true).unwrap();
+
+        let first = &client.savegame_writers[&HandleKey::new(1, 1)];
+        let second = &client.savegame_writers[&HandleKey::new(2, 2)];
+
+        assert!(first.write(b"12345").is_ok());
+        assert!(second.write(b"1234").is_err());
+        assert!(second.write(b"123").is_ok());
+
+        first.close().unwrap();
+        client.storage.remove(None, 1).unwrap();
+
+        assert!(second.write(b"45678").is_ok());
+    }
+
+    #[test]
+    fn saves_are_locked_per_id() {
+        let mut client = client(0, Vec::new());
+
+        let save = client.storage.open_write(None, 1, true).unwrap();
+        client.storage.commit(None, 1, save).unwrap();
+
+        client.get_savegame_reader(1, 0, 1).unwrap();
+        client.get_savegame_reader(2, 0, 1).unwrap();
+
+        assert!(client.get_savegame_writer(3, 0, 1, true).is_err());
+        assert!(client.get_savegame_writer(4, 0, 2, true).is_ok());
+        assert!(client.get_savegame_reader(5, 0, 2).is_err());
+        assert!(client.get_savegame_reader(1, 0, 1).is_err());
+
+        for reader in client.savegame_readers.values() {
+            reader.close();
+        }
+
+        assert!(client.get_savegame_writer(6, 0, 1, true).is_ok());
+    }
+
+    #[test]
+    fn cd_keys_per_product() {
+        let mut client = client(100, vec![200, 300]);
+        client.cd_keys.insert(100, vec![Ustr::from("GAME")]);
+        client.cd_keys.insert(200, vec![Ustr::from("DLC")]);
+
+        assert_eq!(client.get_cd_keys(100).unwrap(), &[Ustr::from("GAME")]);
+        assert_eq!(client.get_cd_keys(200).unwrap(), &[Ustr::from("DLC")]);
+        assert!(client.get_cd_keys(300).unwrap().is_empty());
+        assert!(client.get_cd_keys(400).is_err());
+    }
+
+    #[test]
+    fn unconfigured_product_allows_every_product() {
+        let client = client(0, Vec::new());
+
+        assert!(client.is_product_allowed(12345));
+        assert_eq!(client.get_storage_product(12345).unwrap(), None);
+    }
+
+    #[test]
+    fn empty_allow_list_allows_only_the_game() {
+        let client = client(100, Vec::new());
+
+        assert!(client.is_product_allowed(100));
+        assert!(!client.is_product_allowed(200));
+        assert!(client.get_storage_product(200).is_err());
+        assert_eq!(client.get_storage_product(0).unwrap(), None);
+        assert_eq!(client.get_storage_product(100).unwrap(), None);
+    }
+}
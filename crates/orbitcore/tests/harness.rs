@@ -0,0 +1,1070 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::{fs, ptr};
+
+use orbitcore::api::*;
+use orbitcore::consts::SAVES_MANIFEST_NAME;
+use orbitcore::error::ErrorCode;
+use orbitcore::listeners::{
+    GetCdKeysListener, GetSavegameListListener, GetSavegameReaderListener,
+    GetSavegameWriterListener, RemoveSavegameListener, SavegameReadListener, SavegameWriteListener,
+};
+use orbitcore::models::config::Quota;
+use orbitcore::storage::{FsStorage, SaveStorage};
+use orbitcore::types::{HandleKey, OrbitClient, SavegameInfo, SavegameReader, SavegameWriter};
+use tempfile::{tempdir, TempDir};
+use ustr::Ustr;
+use widestring::U16CString;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    SavegameList(u32, Vec<(u32, u32, String)>),
+    SavegameWriter(u32, u32, *const SavegameWriter),
+    SavegameReader(u32, u32, *const SavegameReader),
+    RemoveSavegame(u32, bool),
+    SavegameRead(u32, u32),
+    SavegameWrite(u32, u32),
+    CdKeys(u32, Vec<String>),
+}
+
+#[derive(Default)]
+struct Recorder {
+    events: RefCell<Vec<Event>>,
+}
+
+impl Recorder {
+    fn take(&self) -> Vec<Event> {
+        self.events.borrow_mut().drain(..).collect()
+    }
+
+    fn push(&self, event: Event) {
+        self.events.borrow_mut().push(event);
+    }
+}
+
+impl GetSavegameListListener for Recorder {
+    fn callback(&self, request_id: u32, savegame_info_list: &[Box<SavegameInfo>]) {
+        let list = savegame_info_list
+            .iter()
+            .map(|info| (info.id, info.size, info.name.to_string().unwrap()))
+            .collect();
+
+        self.push(Event::SavegameList(request_id, list));
+    }
+}
+
+impl GetSavegameWriterListener for Recorder {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_writer: Option<&SavegameWriter>) {
+        let savegame_writer = savegame_writer.map_or(ptr::null(), |writer| writer as *const _);
+
+        self.push(Event::SavegameWriter(
+            request_id,
+            error_code,
+            savegame_writer,
+        ));
+    }
+}
+
+impl GetSavegameReaderListener for Recorder {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_reader: Option<&SavegameReader>) {
+        let savegame_reader = savegame_reader.map_or(ptr::null(), |reader| reader as *const _);
+
+        self.push(Event::SavegameReader(
+            request_id,
+            error_code,
+            savegame_reader,
+        ));
+    }
+}
+
+impl RemoveSavegameListener for Recorder {
+    fn callback(&self, request_id: u32, removed: bool) {
+        self.push(Event::RemoveSavegame(request_id, removed));
+    }
+}
+
+impl SavegameReadListener for Recorder {
+    fn callback(&self, request_id: u32, bytes_read: u32) {
+        self.push(Event::SavegameRead(request_id, bytes_read));
+    }
+}
+
+impl SavegameWriteListener for Recorder {
+    fn callback(&self, request_id: u32, bytes_written: u32) {
+        self.push(Event::SavegameWrite(request_id, bytes_written));
+    }
+}
+
+impl GetCdKeysListener for Recorder {
+    fn callback(&self, request_id: u32, cd_keys: &[Ustr]) {
+        let cd_keys = cd_keys.iter().map(|cd_key| cd_key.to_string()).collect();
+
+        self.push(Event::CdKeys(request_id, cd_keys));
+    }
+}
+
+/// A game talking to the emulated client, with a throwaway saves folder.
+struct Game {
+    _dir: TempDir,
+    saves_path: PathBuf,
+    client: OrbitClient,
+    listener: Recorder,
+}
+
+impl Game {
+    fn new() -> Self {
+        Self::with_storage(|saves_path| Rc::new(FsStorage::new(saves_path.to_owned())))
+    }
+
+    fn with_storage(storage: impl FnOnce(&Path) -> Rc<dyn SaveStorage>) -> Self {
+        let dir = tempdir().unwrap();
+        let saves_path = dir.path().join("Saves");
+        let storage = storage(&saves_path);
+        let client = OrbitClient::new(storage, 0, Vec::new());
+
+        Self {
+            _dir: dir,
+            saves_path,
+            client,
+            listener: Recorder::default(),
+        }
+    }
+
+    fn saves_path(&self) -> &Path {
+        &self.saves_path
+    }
+
+    fn request_id(&mut self) -> u32 {
+        orbit_client_get_request_unique_id(&mut self.client)
+    }
+
+    fn list(&mut self) -> Vec<Event> {
+        let request_id = self.request_id();
+        orbit_client_get_savegame_list(&mut self.client, request_id, &self.listener, 0);
+        self.listener.take()
+    }
+
+    fn writer(&self, id: u32, request_id: u32) -> &SavegameWriter {
+        &self.client.savegame_writers[&HandleKey::new(id, request_id)]
+    }
+
+    fn reader(&self, id: u32, request_id: u32) -> &SavegameReader {
+        &self.client.savegame_readers[&HandleKey::new(id, request_id)]
+    }
+
+    fn save(&mut self, id: u32, name: &str, chunks: &[&[u8]]) {
+        let request_id = self.request_id();
+        orbit_client_get_savegame_writer(&mut self.client, request_id, &self.listener, 0, id, true);
+        self.listener.take();
+
+        let writer = self.writer(id, request_id);
+
+        for chunk in chunks {
+            savegame_writer_write(writer, 0, &self.listener, chunk);
+        }
+
+        let name = U16CString::from_str(name).unwrap();
+        assert!(savegame_writer_set_name(writer, &name));
+
+        savegame_writer_close(writer);
+        self.listener.take();
+    }
+
+    fn read(&mut self, id: u32, offset: u32, len: usize) -> (Vec<Event>, Vec<u8>) {
+        let request_id = self.request_id();
+        orbit_client_get_savegame_reader(&mut self.client, request_id, &self.listener, 0, id);
+        self.listener.take();
+
+        let reader = self.reader(id, request_id);
+        let mut buffer = vec![0xAAu8; len];
+
+        savegame_reader_read(reader, 7, &self.listener, offset, &mut buffer);
+        savegame_reader_close(reader);
+
+        (self.listener.take(), buffer)
+    }
+}
+
+#[test]
+fn full_savegame_round_trip() {
+    let mut game = Game::new();
+
+    assert_eq!(game.list(), vec![Event::SavegameList(1, Vec::new())]);
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+    let writer = game.writer(1, request_id);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWriter(request_id, 0, writer)]
+    );
+
+    savegame_writer_write(writer, 10, &game.listener, b"hello");
+    assert!(savegame_writer_set_name(
+        writer,
+        &U16CString::from_str("Chapter 1").unwrap()
+    ));
+    savegame_writer_close(writer);
+
+    assert_eq!(game.listener.take(), vec![Event::SavegameWrite(10, 5)]);
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(3, vec![(1, 5, "Chapter 1".to_owned())])]
+    );
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_reader(&mut game.client, request_id, &game.listener, 0, 1);
+    let reader = game.reader(1, request_id);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameReader(request_id, 0, reader)]
+    );
+
+    let mut buffer = [0u8; 5];
+    savegame_reader_read(reader, 11, &game.listener, 0, &mut buffer);
+
+    assert_eq!(game.listener.take(), vec![Event::SavegameRead(11, 5)]);
+    assert_eq!(&buffer, b"hello");
+
+    savegame_reader_close(reader);
+
+    let request_id = game.request_id();
+    orbit_client_remove_savegame(&game.client, request_id, &game.listener, 0, 1);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::RemoveSavegame(request_id, true)]
+    );
+    assert_eq!(game.list(), vec![Event::SavegameList(6, Vec::new())]);
+}
+
+#[test]
+fn request_ids_are_unique() {
+    let mut game = Game::new();
+    let ids = (0..5).map(|_| game.request_id()).collect::<Vec<_>>();
+
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn rename_keeps_single_entry() {
+    let mut game = Game::new();
+
+    game.save(3, "Old", &[b"data"]);
+    game.save(3, "New", &[b"data"]);
+
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(3, vec![(3, 4, "New".to_owned())])]
+    );
+}
+
+#[test]
+fn read_at_offsets() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"0123456789"]);
+
+    let (events, buffer) = game.read(1, 0, 4);
+    assert_eq!(events, vec![Event::SavegameRead(7, 4)]);
+    assert_eq!(buffer, b"0123");
+
+    let (events, buffer) = game.read(1, 6, 4);
+    assert_eq!(events, vec![Event::SavegameRead(7, 4)]);
+    assert_eq!(buffer, b"6789");
+}
+
+#[test]
+fn read_past_end_of_file() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"0123456789"]);
+
+    let (events, buffer) = game.read(1, 8, 4);
+    assert_eq!(events, vec![Event::SavegameRead(7, 2)]);
+    assert_eq!(buffer, [b'8', b'9', 0xAA, 0xAA]);
+
+    let (events, buffer) = game.read(1, 10, 4);
+    assert_eq!(events, vec![Event::SavegameRead(7, 0)]);
+    assert_eq!(buffer, [0xAA; 4]);
+
+    let (events, _) = game.read(1, 100, 4);
+    assert_eq!(events, vec![Event::SavegameRead(7, 0)]);
+}
+
+#[test]
+fn read_zero_bytes() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"0123456789"]);
+
+    let (events, buffer) = game.read(1, 0, 0);
+
+    assert_eq!(events, vec![Event::SavegameRead(7, 0)]);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn read_missing_save_is_rejected() {
+    let mut game = Game::new();
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_reader(&mut game.client, request_id, &game.listener, 0, 9);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameReader(
+            request_id,
+            ErrorCode::NotFound as u32,
+            ptr::null()
+        )]
+    );
+    assert!(game.client.savegame_readers.is_empty());
+}
+
+#[test]
+fn read_after_close_reports_zero_bytes() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"0123456789"]);
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_reader(&mut game.client, request_id, &game.listener, 0, 1);
+    game.listener.take();
+
+    let reader = game.reader(1, request_id);
+    let mut buffer = [0u8; 4];
+
+    savegame_reader_close(reader);
+    savegame_reader_read(reader, 2, &game.listener, 0, &mut buffer);
+
+    assert_eq!(game.listener.take(), vec![Event::SavegameRead(2, 0)]);
+}
+
+#[test]
+fn list_ignores_unknown_files() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"1"]);
+
+    let saves_path = game.saves_path().to_owned();
+    fs::write(saves_path.join("2.save"), b"no manifest entry").unwrap();
+    fs::write(saves_path.join("backup.save"), b"not an id").unwrap();
+    fs::write(saves_path.join("3.bak"), b"wrong extension").unwrap();
+    fs::create_dir(saves_path.join("4.save")).unwrap();
+
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(2, vec![(1, 1, "Save".to_owned())])]
+    );
+}
+
+#[test]
+fn list_ignores_manifest_entries_without_file() {
+    let mut game = Game::new();
+    game.save(1, "First", &[b"1"]);
+    game.save(2, "Second", &[b"22"]);
+
+    fs::remove_file(game.saves_path().join("1.save")).unwrap();
+
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(3, vec![(2, 2, "Second".to_owned())])]
+    );
+}
+
+#[test]
+fn list_without_manifest_is_empty() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"1"]);
+
+    fs::remove_file(game.saves_path().join(SAVES_MANIFEST_NAME)).unwrap();
+
+    assert_eq!(game.list(), vec![Event::SavegameList(2, Vec::new())]);
+}
+
+#[test]
+fn remove_missing_save_reports_not_removed() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"1"]);
+
+    let request_id = game.request_id();
+    orbit_client_remove_savegame(&game.client, request_id, &game.listener, 0, 2);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::RemoveSavegame(request_id, false)]
+    );
+}
+
+#[test]
+fn broken_manifest_reports_empty_list() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"1"]);
+
+    fs::write(game.saves_path().join(SAVES_MANIFEST_NAME), "Saves = 1").unwrap();
+
+    assert_eq!(game.list(), vec![Event::SavegameList(2, Vec::new())]);
+}
+
+#[test]
+fn failed_write_reports_zero_bytes() {
+    let mut game = Game::new();
+
+    fs::write(game.saves_path(), b"not a directory").unwrap();
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+    game.listener.take();
+
+    savegame_writer_write(game.writer(1, request_id), 2, &game.listener, b"data");
+
+    assert_eq!(game.listener.take(), vec![Event::SavegameWrite(2, 0)]);
+}
+
+#[test]
+fn other_products_are_rejected() {
+    let mut game = Game::new();
+    game.client.product_id = 100;
+    game.client.product_ids = vec![200];
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 300, 1, true);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWriter(
+            request_id,
+            ErrorCode::AccessDenied as u32,
+            ptr::null()
+        )]
+    );
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_list(&mut game.client, request_id, &game.listener, 300);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameList(request_id, Vec::new())]
+    );
+}
+
+#[test]
+fn cd_keys_are_served_per_product() {
+    let mut game = Game::new();
+    game.client.product_id = 100;
+    game.client.product_ids = vec![200];
+    game.client
+        .cd_keys
+        .insert(100, vec![Ustr::from("GAME-KEY")]);
+
+    orbit_client_get_cd_keys(&game.client, 1, &game.listener, 100);
+    orbit_client_get_cd_keys(&game.client, 2, &game.listener, 200);
+    orbit_client_get_cd_keys(&game.client, 3, &game.listener, 300);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![
+            Event::CdKeys(1, vec!["GAME-KEY".to_owned()]),
+            Event::CdKeys(2, Vec::new()),
+            Event::CdKeys(3, Vec::new()),
+        ]
+    );
+}
+
+/// Keeps the list pointer like a game would, to read it after the callback.
+#[derive(Default)]
+struct ListKeeper {
+    list: RefCell<Option<(*const Box<SavegameInfo>, usize)>>,
+}
+
+impl GetSavegameListListener for ListKeeper {
+    fn callback(&self, _request_id: u32, savegame_info_list: &[Box<SavegameInfo>]) {
+        *self.list.borrow_mut() = Some((savegame_info_list.as_ptr(), savegame_info_list.len()));
+    }
+}
+
+impl ListKeeper {
+    /// Reads the kept list; only valid until the next list request.
+    unsafe fn read(&self) -> Vec<(u32, u32, String)> {
+        let (ptr, len) = self.list.borrow().unwrap();
+
+        std::slice::from_raw_parts(ptr, len)
+            .iter()
+            .map(|info| (info.id, info.size, info.name.to_string().unwrap()))
+            .collect()
+    }
+}
+
+#[test]
+fn list_outlives_callback() {
+    let mut game = Game::new();
+    game.save(1, "First", &[b"1"]);
+    game.save(2, "Second", &[b"22"]);
+
+    let keeper = ListKeeper::default();
+    orbit_client_get_savegame_list(&mut game.client, 1, &keeper, 0);
+
+    game.read(1, 0, 1);
+    game.save(3, "Third", &[b"333"]);
+
+    let mut list = unsafe { keeper.read() };
+    list.sort();
+
+    assert_eq!(
+        list,
+        vec![(1, 1, "First".to_owned()), (2, 2, "Second".to_owned())]
+    );
+}
+
+#[test]
+fn next_list_request_replaces_list() {
+    let mut game = Game::new();
+    game.save(1, "First", &[b"1"]);
+
+    let keeper = ListKeeper::default();
+    orbit_client_get_savegame_list(&mut game.client, 1, &keeper, 0);
+
+    game.save(2, "Second", &[b"22"]);
+    orbit_client_get_savegame_list(&mut game.client, 2, &keeper, 0);
+
+    assert_eq!(unsafe { keeper.read() }.len(), 2);
+    assert_eq!(game.client.savegame_info_list.len(), 2);
+}
+
+#[test]
+fn handles_stay_valid_until_closed() {
+    let mut game = Game::new();
+    game.save(1, "First", &[b"1"]);
+
+    let first = game.request_id();
+    orbit_client_get_savegame_reader(&mut game.client, first, &game.listener, 0, 1);
+    let second = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, second, &game.listener, 0, 2, true);
+
+    let events = game.listener.take();
+    let (reader, writer) = match events.as_slice() {
+        [Event::SavegameReader(_, 0, reader), Event::SavegameWriter(_, 0, writer)] => {
+            (*reader, *writer)
+        }
+        events => panic!("Unexpected events: {:?}", events),
+    };
+
+    game.list();
+    orbit_client_update(&mut game.client);
+
+    let mut buffer = [0u8; 1];
+    unsafe {
+        savegame_writer_write(&*writer, 3, &game.listener, b"22");
+        savegame_reader_read(&*reader, 4, &game.listener, 0, &mut buffer);
+        savegame_writer_close(&*writer);
+        savegame_reader_close(&*reader);
+    }
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWrite(3, 2), Event::SavegameRead(4, 1)]
+    );
+
+    orbit_client_update(&mut game.client);
+
+    assert!(game.client.savegame_readers.is_empty());
+    assert!(game.client.savegame_writers.is_empty());
+}
+
+#[test]
+fn writer_waits_for_readers_of_the_same_save() {
+    let mut game = Game::new();
+    game.save(1, "First", &[b"1"]);
+
+    let reader_request_id = game.request_id();
+    orbit_client_get_savegame_reader(&mut game.client, reader_request_id, &game.listener, 0, 1);
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+
+    assert_eq!(
+        game.listener.take().last(),
+        Some(&Event::SavegameWriter(
+            request_id,
+            ErrorCode::InUse as u32,
+            ptr::null()
+        ))
+    );
+
+    savegame_reader_close(game.reader(1, reader_request_id));
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWriter(
+            request_id,
+            0,
+            game.writer(1, request_id)
+        )]
+    );
+}
+
+#[test]
+fn save_written_in_many_chunks() {
+    let mut game = Game::new();
+    let data = (0..4096u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let chunks = data.chunks(37).collect::<Vec<_>>();
+
+    game.save(1, "Chunked", &chunks);
+
+    assert_eq!(fs::read(game.saves_path().join("1.save")).unwrap(), data);
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(
+            2,
+            vec![(1, 4096, "Chunked".to_owned())]
+        )]
+    );
+}
+
+#[test]
+fn rewrite_without_truncate_overwrites_from_start() {
+    let mut game = Game::new();
+    game.save(1, "Save", &[b"hello ", b"world"]);
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, false);
+    let writer = game.writer(1, request_id);
+
+    savegame_writer_write(writer, 3, &game.listener, b"J");
+    savegame_writer_write(writer, 4, &game.listener, b"ELLO");
+    savegame_writer_close(writer);
+
+    assert_eq!(
+        fs::read(game.saves_path().join("1.save")).unwrap(),
+        b"JELLO world"
+    );
+}
+
+#[test]
+fn slot_quota_is_reported_to_the_writer_listener() {
+    let mut game = Game::new();
+    game.client.quota = Quota {
+        max_slots: Some(1),
+        ..Default::default()
+    };
+    game.save(1, "First", &[b"1"]);
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 2, true);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWriter(
+            request_id,
+            ErrorCode::OutOfSpace as u32,
+            ptr::null()
+        )]
+    );
+}
+
+#[test]
+fn read_only_session_refuses_writers_and_removal() {
+    use orbitcore::storage::ReadOnlyStorage;
+
+    let mut game = Game::new();
+    game.save(1, "Reference", &[b"0123"]);
+    game.client.storage = Rc::new(ReadOnlyStorage::new(game.client.storage.clone()));
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::SavegameWriter(
+            request_id,
+            ErrorCode::AccessDenied as u32,
+            ptr::null()
+        )]
+    );
+
+    let request_id = game.request_id();
+    orbit_client_remove_savegame(&game.client, request_id, &game.listener, 0, 1);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::RemoveSavegame(request_id, false)]
+    );
+
+    let (events, buffer) = game.read(1, 0, 4);
+
+    assert_eq!(events, vec![Event::SavegameRead(7, 4)]);
+    assert_eq!(buffer, b"0123");
+}
+
+#[test]
+fn sandbox_leaves_the_saves_folder_untouched() {
+    use orbitcore::models::config::{Orbit, Storage};
+    use orbitcore::storage::open_storage;
+
+    let mut game = Game::new();
+    game.save(1, "Snapshot", &[b"before"]);
+
+    let snapshot = |saves_path: &Path| {
+        let mut files = fs::read_dir(saves_path)
+            .unwrap()
+            .map(|entry| {
+                let path = entry.unwrap().path();
+                let data = fs::read(&path).unwrap();
+                (path, data)
+            })
+            .collect::<Vec<_>>();
+
+        files.sort();
+        files
+    };
+    let before = snapshot(game.saves_path());
+
+    let orbit = Orbit {
+        storage: Storage {
+            sandbox: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    game.client = OrbitClient::new(
+        open_storage(&orbit, game.saves_path().to_owned()).unwrap(),
+        0,
+        Vec::new(),
+    );
+
+    game.save(1, "Changed", &[b"after"]);
+    game.save(2, "New", &[b"new"]);
+
+    let request_id = game.request_id();
+    orbit_client_remove_savegame(&game.client, request_id, &game.listener, 0, 2);
+    game.listener.take();
+
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(4, vec![(1, 5, "Changed".to_owned())])]
+    );
+
+    game.client = OrbitClient::default();
+
+    assert_eq!(snapshot(game.saves_path()), before);
+}
+
+#[test]
+fn names_reach_the_manifest_on_update() {
+    let mut game = Game::new();
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 1, true);
+    game.listener.take();
+
+    let writer = game.writer(1, request_id);
+    savegame_writer_write(writer, 0, &game.listener, b"data");
+    assert!(savegame_writer_set_name(
+        writer,
+        &U16CString::from_str("Pending").unwrap()
+    ));
+    game.listener.take();
+
+    let manifest_path = game.saves_path().join(SAVES_MANIFEST_NAME);
+
+    assert!(!manifest_path.exists());
+
+    orbit_client_update(&mut game.client);
+
+    assert!(fs::read_to_string(&manifest_path)
+        .unwrap()
+        .contains("Pending"));
+}
+
+#[test]
+fn removed_save_can_be_restored_from_the_trash() {
+    use orbitcore::models::config::Trash;
+    use orbitcore::storage::trash::{read_trash, restore_save};
+    use orbitcore::storage::TrashStorage;
+
+    let mut game = Game::with_storage(|saves_path| {
+        let inner = Rc::new(FsStorage::new(saves_path.to_owned()));
+        Rc::new(TrashStorage::open(inner, &Trash::default(), 0, saves_path))
+    });
+    game.save(1, "Chapter 1", &[b"hello"]);
+
+    let request_id = game.request_id();
+    orbit_client_remove_savegame(&game.client, request_id, &game.listener, 0, 1);
+
+    assert_eq!(
+        game.listener.take(),
+        vec![Event::RemoveSavegame(request_id, true)]
+    );
+    assert!(!game.saves_path().join("1.save").exists());
+
+    let trashed = read_trash(game.saves_path()).unwrap().saves;
+
+    assert_eq!(trashed.len(), 1);
+    assert_eq!(trashed[0].name, "Chapter 1");
+
+    let save = restore_save(
+        &*game.client.storage,
+        0,
+        game.saves_path(),
+        trashed[0].number,
+    )
+    .unwrap();
+
+    assert_eq!(save.id, 1);
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(3, vec![(1, 5, "Chapter 1".to_owned())])]
+    );
+}
+
+#[test]
+#[cfg(all(feature = "sqlite", not(miri)))]
+fn sqlite_keeps_unclosed_writes_out_of_the_list() {
+    use orbitcore::consts::SAVES_DATABASE_NAME;
+    use orbitcore::storage::SqliteStorage;
+
+    let mut game =
+        Game::with_storage(|saves_path| Rc::new(SqliteStorage::open(saves_path).unwrap()));
+    game.save(1, "Save", &[b"0123", b"456789"]);
+
+    let request_id = game.request_id();
+    orbit_client_get_savegame_writer(&mut game.client, request_id, &game.listener, 0, 2, true);
+    game.listener.take();
+
+    let writer = game.writer(2, request_id);
+    savegame_writer_write(writer, 0, &game.listener, b"partial");
+    assert!(savegame_writer_set_name(
+        writer,
+        &U16CString::from_str("Partial").unwrap()
+    ));
+    game.listener.take();
+
+    assert!(game.saves_path().join(SAVES_DATABASE_NAME).exists());
+    assert!(!game.saves_path().join(SAVES_MANIFEST_NAME).exists());
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(3, vec![(1, 10, "Save".to_owned())])]
+    );
+
+    let (_, buffer) = game.read(1, 0, 10);
+
+    assert_eq!(buffer, b"0123456789");
+}
+
+#[cfg(all(feature = "sync", not(miri)))]
+mod mock {
+    use std::collections::HashMap;
+    use std::rc::Rc;
+    use std::sync::{Arc, Mutex};
+    use std::thread::{self, JoinHandle};
+
+    use orbitcore::models::config::{CloudSync, Protocol};
+    use orbitcore::storage::sync::HttpRemote;
+    use orbitcore::storage::{FsStorage, SyncStorage};
+    use tiny_http::{Header, Method, Request, Response, Server};
+
+    use super::Game;
+
+    /// The contents and tag of each file.
+    type Files = HashMap<String, (Vec<u8>, u64)>;
+
+    /// An HTTP and WebDAV server keeping its files in memory. Files are tagged
+    /// with the number of the write that created them.
+    pub struct MockServer {
+        server: Arc<Server>,
+        files: Arc<Mutex<Files>>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    fn get_header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str())
+    }
+
+    impl MockServer {
+        pub fn start() -> Self {
+            let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+            let files = Arc::new(Mutex::new(Files::new()));
+
+            let thread = {
+                let server = server.clone();
+                let files = files.clone();
+
+                thread::spawn(move || {
+                    let mut last_tag = 0;
+
+                    for mut request in server.incoming_requests() {
+                        let path = request.url().to_owned();
+                        let mut files = files.lock().unwrap();
+                        let tag = files.get(&path).map(|(_, tag)| format!("\"{}\"", tag));
+
+                        let (status, data, tag) = match request.method() {
+                            Method::Get => match files.get(&path) {
+                                Some((data, _)) => (200, data.clone(), tag),
+                                None => (404, Vec::new(), None),
+                            },
+                            Method::Put => {
+                                let if_match = get_header(&request, "If-Match");
+                                let if_none_match = get_header(&request, "If-None-Match");
+
+                                let changed = if_match.is_some() && if_match != tag.as_deref();
+                                let exists = if_none_match == Some("*") && tag.is_some();
+
+                                if changed || exists {
+                                    (412, Vec::new(), None)
+                                } else {
+                                    let mut data = Vec::new();
+                                    request.as_reader().read_to_end(&mut data).unwrap();
+                                    last_tag += 1;
+                                    files.insert(path, (data, last_tag));
+                                    (201, Vec::new(), Some(format!("\"{}\"", last_tag)))
+                                }
+                            }
+                            Method::Delete => match files.remove(&path) {
+                                Some(_) => (204, Vec::new(), None),
+                                None => (404, Vec::new(), None),
+                            },
+                            Method::NonStandard(method) if method.as_str() == "MKCOL" => {
+                                match files.insert(path, (Vec::new(), 0)) {
+                                    Some(_) => (405, Vec::new(), None),
+                                    None => (201, Vec::new(), None),
+                                }
+                            }
+                            _ => (405, Vec::new(), None),
+                        };
+
+                        let mut response = Response::from_data(data).with_status_code(status);
+
+                        if let Some(tag) = tag {
+                            response.add_header(Header::from_bytes("ETag", tag).unwrap());
+                        }
+
+                        request.respond(response).unwrap();
+                    }
+                })
+            };
+
+            Self {
+                server,
+                files,
+                thread: Some(thread),
+            }
+        }
+
+        pub fn has_file(&self, path: &str) -> bool {
+            self.files.lock().unwrap().contains_key(path)
+        }
+
+        fn get_sync(&self, protocol: Protocol) -> CloudSync {
+            CloudSync {
+                url: format!("http://{}/saves", self.server.server_addr()),
+                protocol,
+                ..Default::default()
+            }
+        }
+
+        pub fn remote(&self, protocol: Protocol) -> HttpRemote {
+            HttpRemote::new(&self.get_sync(protocol))
+        }
+
+        pub fn game(&self, protocol: Protocol) -> Game {
+            let sync = self.get_sync(protocol);
+
+            Game::with_storage(|saves_path| {
+                let storage = Rc::new(FsStorage::new(saves_path.to_owned()));
+                Rc::new(SyncStorage::open(storage, &sync, 0, saves_path))
+            })
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.server.unblock();
+
+            if let Some(thread) = self.thread.take() {
+                thread.join().unwrap();
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(all(feature = "sync", not(miri)))]
+fn saves_sync_through_a_server() {
+    use orbitcore::models::config::Protocol;
+
+    let server = mock::MockServer::start();
+    let mut first = server.game(Protocol::WebDav);
+    let mut second = server.game(Protocol::Http);
+
+    first.save(1, "Chapter 1", &[b"hello"]);
+
+    assert!(server.has_file("/saves/0/"));
+    assert!(server.has_file("/saves/0/1.1.save"));
+    assert!(server.has_file("/saves/0/Index.toml"));
+    assert_eq!(
+        second.list(),
+        vec![Event::SavegameList(1, vec![(1, 5, "Chapter 1".to_owned())])]
+    );
+
+    let (_, buffer) = second.read(1, 0, 5);
+
+    assert_eq!(buffer, b"hello");
+
+    second.save(1, "Chapter 2", &[b"world"]);
+
+    assert_eq!(
+        first.list(),
+        vec![Event::SavegameList(2, vec![(1, 5, "Chapter 2".to_owned())])]
+    );
+}
+
+#[test]
+#[cfg(all(feature = "sync", not(miri)))]
+fn server_refuses_stale_writes() {
+    use orbitcore::models::config::Protocol;
+    use orbitcore::models::sync::Version;
+    use orbitcore::storage::sync::Remote;
+
+    let server = mock::MockServer::start();
+    let remote = server.remote(Protocol::Http);
+    let (_, missing) = remote.get_versioned("0/Index.toml").unwrap();
+
+    assert_eq!(missing, Version::Missing);
+
+    let written = remote
+        .put_versioned("0/Index.toml", b"", &missing)
+        .unwrap()
+        .unwrap();
+
+    assert!(remote
+        .put_versioned("0/Index.toml", b"", &missing)
+        .unwrap()
+        .is_none());
+    assert_eq!(remote.get_versioned("0/Index.toml").unwrap().1, written);
+    assert!(remote
+        .put_versioned("0/Index.toml", b"", &written)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+#[cfg(all(feature = "sync", not(miri)))]
+fn unreachable_server_keeps_local_saves() {
+    use orbitcore::models::config::Protocol;
+
+    let server = mock::MockServer::start();
+    let mut game = server.game(Protocol::Http);
+    drop(server);
+
+    game.save(1, "Save", &[b"data"]);
+
+    assert_eq!(
+        game.list(),
+        vec![Event::SavegameList(2, vec![(1, 4, "Save".to_owned())])]
+    );
+}
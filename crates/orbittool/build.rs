@@ -0,0 +1,24 @@
+use std::path::Path;
+use std::{env, fs};
+
+use cxxgen::exports::read_exports;
+
+fn main() {
+    let api_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../src/api.rs");
+    let out_path = Path::new(&env::var("OUT_DIR").unwrap()).join("exports.rs");
+
+    println!("cargo:rerun-if-changed={}", api_path.display());
+
+    let source = fs::read_to_string(&api_path).unwrap();
+    let exports = read_exports(&source)
+        .unwrap()
+        .iter()
+        .map(|export| format!("    {:?},\n", export.symbol))
+        .collect::<String>();
+
+    fs::write(
+        out_path,
+        format!("pub const EXPORTS: &[&str] = &[\n{}];\n", exports),
+    )
+    .unwrap();
+}
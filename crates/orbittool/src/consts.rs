@@ -0,0 +1,2 @@
+pub const ORBIT_DLL_NAME: &str = "ubiorbitapi_r2.dll";
+pub const ORBIT_SYMBOL_NAMESPACE: &str = "@orbitclient@mg@@";
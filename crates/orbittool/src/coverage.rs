@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use goblin::pe::PE;
+use msvc_demangler::DemangleFlags;
+
+use crate::consts::{ORBIT_DLL_NAME, ORBIT_SYMBOL_NAMESPACE};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Coverage {
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+/// Collects the Orbit symbols a PE file needs or provides: its
+/// `ubiorbitapi_r2.dll` imports, or the exports of a DLL that imports nothing
+/// from it, such as the real `ubiorbitapi_r2.dll`. Game code shipped as a DLL
+/// is compared by its imports like an executable.
+pub fn read_pe_symbols(data: &[u8]) -> Result<Vec<String>> {
+    let pe = PE::parse(data)?;
+
+    let imports = pe
+        .imports
+        .iter()
+        .filter(|import| import.dll.eq_ignore_ascii_case(ORBIT_DLL_NAME))
+        .map(|import| import.name.to_string())
+        .collect::<Vec<_>>();
+
+    let exports = pe
+        .exports
+        .iter()
+        .filter_map(|export| export.name)
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+
+    let symbols = select_symbols(imports, exports);
+
+    if symbols.is_empty() {
+        return Err(anyhow!("No exports or {} imports found!", ORBIT_DLL_NAME));
+    }
+
+    Ok(symbols
+        .into_iter()
+        .filter(|symbol| symbol.contains(ORBIT_SYMBOL_NAMESPACE))
+        .collect())
+}
+
+#[inline]
+fn select_symbols(imports: Vec<String>, exports: Vec<String>) -> Vec<String> {
+    if imports.is_empty() {
+        exports
+    } else {
+        imports
+    }
+}
+
+pub fn compare(target: &[String], exported: &[&str]) -> Coverage {
+    let target = target.iter().map(String::as_str).collect::<BTreeSet<_>>();
+    let exported = exported.iter().copied().collect::<BTreeSet<_>>();
+
+    Coverage {
+        missing: target
+            .difference(&exported)
+            .map(|&symbol| symbol.to_owned())
+            .collect(),
+        extra: exported
+            .difference(&target)
+            .map(|&symbol| symbol.to_owned())
+            .collect(),
+    }
+}
+
+pub fn demangle(symbol: &str) -> String {
+    msvc_demangler::demangle(symbol, DemangleFlags::llvm()).unwrap_or_else(|_| symbol.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CTOR: &str = "??0OrbitClient@orbitclient@mg@@QAE@XZ";
+    const UPDATE: &str = "?Update@OrbitClient@orbitclient@mg@@QAEXXZ";
+    const DTOR: &str = "??1OrbitClient@orbitclient@mg@@QAE@XZ";
+
+    #[test]
+    fn compare_reports_missing_and_extra() {
+        let target = vec![CTOR.to_owned(), UPDATE.to_owned()];
+        let coverage = compare(&target, &[CTOR, DTOR]);
+
+        assert_eq!(coverage.missing, vec![UPDATE]);
+        assert_eq!(coverage.extra, vec![DTOR]);
+    }
+
+    #[test]
+    fn compare_full_coverage() {
+        let target = vec![CTOR.to_owned()];
+
+        assert_eq!(compare(&target, &[CTOR]), Coverage::default());
+    }
+
+    #[test]
+    fn demangles_orbit_symbols() {
+        assert_eq!(
+            demangle(UPDATE),
+            "public: void __thiscall mg::orbitclient::OrbitClient::Update(void)"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_symbols() {
+        assert_eq!(demangle("not_mangled"), "not_mangled");
+    }
+
+    #[test]
+    fn orbit_imports_win_over_exports() {
+        let imports = vec![UPDATE.to_owned()];
+        let exports = vec!["GameMain".to_owned(), CTOR.to_owned()];
+
+        assert_eq!(select_symbols(imports, exports.clone()), vec![UPDATE]);
+        assert_eq!(select_symbols(Vec::new(), exports.clone()), exports);
+    }
+
+    #[test]
+    fn rejects_non_pe_files() {
+        assert!(read_pe_symbols(b"not a pe file").is_err());
+    }
+}
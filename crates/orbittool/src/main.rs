@@ -0,0 +1,408 @@
+mod consts;
+mod coverage;
+mod exports;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+use orbitcore::bundle::{export_bundle, import_bundle, Collision};
+use orbitcore::helpers::config::read_config;
+use orbitcore::helpers::official::{
+    export_official_saves, find_official_saves_path, import_official_saves,
+};
+use orbitcore::helpers::save::get_game_saves_path;
+use orbitcore::helpers::time::get_timestamp;
+use orbitcore::models::config::Orbit;
+use orbitcore::storage::trash::{read_trash, restore_save};
+use orbitcore::storage::{open_backend, SqliteStorage};
+use orbitcore::types::OrbitClient;
+
+use crate::coverage::{compare, demangle, read_pe_symbols};
+use crate::exports::EXPORTS;
+
+#[derive(Parser)]
+#[clap(about = "Host-side utilities for the Orbit emulator")]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compare the exports or imports of a PE file with the loader exports
+    Coverage {
+        /// A real ubiorbitapi_r2.dll or a game executable
+        path: PathBuf,
+    },
+    /// Copy the saves and Manifest.toml names of a saves folder into its Saves.db
+    ImportSqlite {
+        /// The configured saves folder
+        saves_path: PathBuf,
+    },
+    /// Copy the saves of a Saves.db back to the folder and Manifest.toml layout
+    ExportSqlite {
+        /// The configured saves folder
+        saves_path: PathBuf,
+    },
+    /// Copy a product's saves from the savegames folder of Ubisoft Connect into
+    /// the saves of the game configured in an Orbit.toml
+    ImportOfficial {
+        #[clap(flatten)]
+        official: Official,
+        #[clap(flatten)]
+        game: Game,
+    },
+    /// Copy a product's saves of the game configured in an Orbit.toml to the
+    /// savegames folder of Ubisoft Connect
+    ExportOfficial {
+        #[clap(flatten)]
+        game: Game,
+        #[clap(flatten)]
+        official: Official,
+    },
+    /// Pack saves of the game configured in an Orbit.toml into a bundle
+    ExportBundle {
+        #[clap(flatten)]
+        game: Game,
+        bundle_path: PathBuf,
+        /// Saves to pack, all of them if none are given
+        ids: Vec<u32>,
+    },
+    /// Unpack a bundle into the saves of the game configured in an Orbit.toml
+    ImportBundle {
+        #[clap(flatten)]
+        game: Game,
+        bundle_path: PathBuf,
+        /// Replace saves with the same id instead of giving the bundled ones
+        /// new ids
+        #[clap(long)]
+        overwrite: bool,
+    },
+    /// List the removed saves of the game configured in an Orbit.toml
+    Trash {
+        /// The Orbit.toml next to the game
+        config_path: PathBuf,
+    },
+    /// Put a removed save back, under a new id if its id was taken since
+    Restore {
+        /// The Orbit.toml next to the game
+        config_path: PathBuf,
+        /// The number listed by `trash`
+        number: u32,
+    },
+}
+
+#[derive(Args)]
+struct Game {
+    /// The Orbit.toml next to the game
+    config_path: PathBuf,
+    /// Another product of the game, such as a DLC
+    #[clap(long)]
+    product_id: Option<u32>,
+}
+
+impl Game {
+    /// Opens the saves themselves, since a sandbox or read-only session of the
+    /// game must not keep the tool from changing them.
+    fn open(&self) -> Result<(Orbit, OrbitClient, Option<u32>)> {
+        let (orbit, saves_path) = read_game_config(&self.config_path)?;
+        let storage = open_backend(&orbit.storage, &saves_path, false)?;
+
+        let client = OrbitClient::new(storage, orbit.product_id, orbit.product_ids.clone());
+        let product_id = self.product_id.unwrap_or(orbit.product_id);
+        let product = client.get_storage_product(product_id)?;
+
+        Ok((orbit, client, product))
+    }
+}
+
+fn read_game_config(config_path: &Path) -> Result<(Orbit, PathBuf)> {
+    let orbit = read_config(config_path)?.orbit;
+    let game_path = config_path.parent().unwrap_or_else(|| Path::new("."));
+    let saves_path = get_game_saves_path(&orbit, game_path)?;
+
+    Ok((orbit, saves_path))
+}
+
+#[derive(Args)]
+struct Official {
+    /// The savegames folder of Ubisoft Connect
+    savegames_path: PathBuf,
+    /// Needed when several users have saves of the product
+    #[clap(long)]
+    user_id: Option<String>,
+}
+
+impl Official {
+    fn find_path(&self, product_id: u32) -> Result<PathBuf> {
+        find_official_saves_path(&self.savegames_path, product_id, self.user_id.as_deref())
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Coverage { path } => coverage(path),
+        Command::ImportSqlite { saves_path } => import_sqlite(saves_path),
+        Command::ExportSqlite { saves_path } => export_sqlite(saves_path),
+        Command::ExportBundle {
+            game,
+            bundle_path,
+            ids,
+        } => export(game, bundle_path, ids),
+        Command::ImportBundle {
+            game,
+            bundle_path,
+            overwrite,
+        } => import(game, bundle_path, overwrite),
+        Command::Trash { config_path } => trash(config_path),
+        Command::Restore {
+            config_path,
+            number,
+        } => restore(config_path, number),
+        Command::ImportOfficial { official, game } => import_official(official, game),
+        Command::ExportOfficial { game, official } => export_official(game, official),
+    }
+}
+
+fn coverage(path: PathBuf) -> Result<()> {
+    let data = fs::read(&path)?;
+    let symbols = read_pe_symbols(&data)?;
+    let coverage = compare(&symbols, EXPORTS);
+
+    println!(
+        "{}: {} symbols, {} exported by the loader",
+        path.display(),
+        symbols.len(),
+        EXPORTS.len()
+    );
+
+    println!("\nMissing ({}):", coverage.missing.len());
+    for symbol in &coverage.missing {
+        println!("  {}\n      {}", symbol, demangle(symbol));
+    }
+
+    println!("\nExtra ({}):", coverage.extra.len());
+    for symbol in &coverage.extra {
+        println!("  {}\n      {}", symbol, demangle(symbol));
+    }
+
+    Ok(())
+}
+
+fn import_sqlite(saves_path: PathBuf) -> Result<()> {
+    let storage = SqliteStorage::open(&saves_path)?;
+    let count = storage.import_folder(&saves_path)?;
+
+    println!("Imported {} saves into {}", count, saves_path.display());
+
+    Ok(())
+}
+
+fn export_sqlite(saves_path: PathBuf) -> Result<()> {
+    let storage = SqliteStorage::open(&saves_path)?;
+    let count = storage.export_folder(&saves_path)?;
+
+    println!("Exported {} saves to {}", count, saves_path.display());
+
+    Ok(())
+}
+
+fn import_official(official: Official, game: Game) -> Result<()> {
+    let (orbit, client, product) = game.open()?;
+    let product_id = product.unwrap_or(orbit.product_id);
+    let official_path = official.find_path(product_id)?;
+    let count = import_official_saves(&official_path, &*client.storage, product)?;
+    client.flush_savegames()?;
+
+    println!(
+        "Imported {} saves of {} from {}",
+        count,
+        product_id,
+        official_path.display()
+    );
+
+    Ok(())
+}
+
+fn export_official(game: Game, official: Official) -> Result<()> {
+    let (orbit, client, product) = game.open()?;
+    let product_id = product.unwrap_or(orbit.product_id);
+    let official_path = official.find_path(product_id)?;
+    let count = export_official_saves(&*client.storage, product, &official_path)?;
+
+    println!(
+        "Exported {} saves of {} to {}",
+        count,
+        product_id,
+        official_path.display()
+    );
+
+    Ok(())
+}
+
+fn export(game: Game, bundle_path: PathBuf, ids: Vec<u32>) -> Result<()> {
+    let (orbit, client, product) = game.open()?;
+    let file = File::create(&bundle_path)?;
+    let bundle = export_bundle(&*client.storage, &orbit, product, &ids, file)?;
+
+    println!(
+        "Packed {} saves of {} ({}) into {}",
+        bundle.saves.len(),
+        bundle.name,
+        bundle.product_id,
+        bundle_path.display()
+    );
+
+    Ok(())
+}
+
+fn import(game: Game, bundle_path: PathBuf, overwrite: bool) -> Result<()> {
+    let (orbit, client, product) = game.open()?;
+    let file = File::open(&bundle_path)?;
+    let product_id = product.unwrap_or(orbit.product_id);
+    let collision = if overwrite {
+        Collision::Overwrite
+    } else {
+        Collision::Remap
+    };
+
+    let ids = import_bundle(&*client.storage, product, product_id, file, collision)?;
+    client.flush_savegames()?;
+
+    for (bundled_id, id) in &ids {
+        if bundled_id != id {
+            println!("Savegame {} is now {}", bundled_id, id);
+        }
+    }
+
+    println!(
+        "Unpacked {} saves from {}",
+        ids.len(),
+        bundle_path.display()
+    );
+
+    Ok(())
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=3599 => format!("{} minutes", seconds / 60),
+        3600..=86399 => format!("{} hours", seconds / 3600),
+        _ => format!("{} days", seconds / 86400),
+    }
+}
+
+fn trash(config_path: PathBuf) -> Result<()> {
+    let (_, saves_path) = read_game_config(&config_path)?;
+    let index = read_trash(&saves_path)?;
+    let now = get_timestamp();
+
+    for save in &index.saves {
+        println!(
+            "{}: {} (savegame {} of {}), removed {} ago",
+            save.number,
+            save.name,
+            save.id,
+            save.product_id,
+            format_age(now.saturating_sub(save.removed))
+        );
+    }
+
+    println!("{} saves in the trash", index.saves.len());
+
+    Ok(())
+}
+
+fn restore(config_path: PathBuf, number: u32) -> Result<()> {
+    let (orbit, saves_path) = read_game_config(&config_path)?;
+    let storage = open_backend(&orbit.storage, &saves_path, false)?;
+    let save = restore_save(&*storage, orbit.product_id, &saves_path, number)?;
+    storage.flush()?;
+
+    println!(
+        "Restored {} as savegame {} of {}",
+        save.name, save.id, save.product_id
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use orbitcore::consts::CONFIG_NAME;
+    use orbitcore::models::config::Trash;
+    use orbitcore::storage::trash::TrashStorage;
+    use orbitcore::storage::{write_save_data, FsStorage, MemoryStorage, SaveStorage};
+    use tempfile::{tempdir, TempDir};
+
+    use super::*;
+
+    /// A game next to the shipped Orbit.toml, set up for sandbox sessions.
+    fn sandbox_game() -> (TempDir, PathBuf) {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join(CONFIG_NAME);
+        let config =
+            include_str!("../../../Orbit.toml").replace("Sandbox = false", "Sandbox = true");
+        fs::write(&config_path, config).unwrap();
+
+        (dir, config_path)
+    }
+
+    fn saves(dir: &TempDir) -> Vec<(u32, String)> {
+        FsStorage::new(dir.path().join("Saves"))
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|save| (save.id, save.name))
+            .collect()
+    }
+
+    #[test]
+    fn restore_ignores_the_sandbox() {
+        let (dir, config_path) = sandbox_game();
+        let saves_path = dir.path().join("Saves");
+        let storage = TrashStorage::open(
+            Rc::new(FsStorage::new(saves_path.clone())),
+            &Trash::default(),
+            0,
+            &saves_path,
+        );
+
+        write_save_data(&storage, None, 1, "Save", b"data").unwrap();
+        storage.remove(None, 1).unwrap();
+        restore(config_path, 1).unwrap();
+
+        assert_eq!(saves(&dir), vec![(1, "Save".to_owned())]);
+    }
+
+    #[test]
+    fn import_bundle_ignores_the_sandbox() {
+        let (dir, config_path) = sandbox_game();
+        let bundle_path = dir.path().join("Saves.tar");
+        let storage = MemoryStorage::default();
+
+        write_save_data(&storage, None, 1, "Save", b"data").unwrap();
+        export_bundle(
+            &storage,
+            &Orbit::default(),
+            None,
+            &[],
+            File::create(&bundle_path).unwrap(),
+        )
+        .unwrap();
+
+        let game = Game {
+            config_path,
+            product_id: None,
+        };
+        import(game, bundle_path, false).unwrap();
+
+        assert_eq!(saves(&dir), vec![(1, "Save".to_owned())]);
+    }
+}
@@ -1,47 +1,72 @@
 #![allow(dead_code)]
 #![allow(unused_variables)]
 
-use std::fs::OpenOptions;
 use std::os::raw::c_char;
-use std::path::PathBuf;
-use std::{ptr, slice};
+use std::rc::Rc;
+use std::slice;
 
-use anyhow::Result;
 use cxxabi::cxxabi;
 use fnlog::fn_debug;
 use log::error;
 use once_cell::sync::Lazy;
+use orbitcore::api;
+use orbitcore::helpers::config::get_cd_keys;
+use orbitcore::helpers::save::get_saves_path;
+use orbitcore::listeners::SavegameWriteListener;
+use orbitcore::storage::{open_storage, SaveStorage, UnavailableStorage};
+use orbitcore::types::{OrbitClient, SavegameInfo, SavegameReader, SavegameWriter};
 use thiscall::{get_this_ptr_cxx, set_this_ptr_cxx};
 use ustr::Ustr;
-use widestring::{U16CStr, U16CString};
+use widestring::U16CStr;
 
+use crate::check_installation;
 use crate::global::CONFIG;
 use crate::helpers::alloc::alloc;
-use crate::helpers::manifest::{read_manifest, write_manifest};
-use crate::helpers::save::{get_save_path, get_saves, read_save, remove_save, write_save};
-use crate::models::manifest::Save;
+use crate::helpers::string::{from_c_ptr, from_wide_ptr};
 use crate::types::{
-    IGetLoginDetailsListener, IGetSavegameListListener, IGetSavegameReaderListener,
-    IGetSavegameWriterListener, IRemoveSavegameListener, ISavegameReadListener,
-    ISavegameWriteListener, OrbitClient, SavegameInfo, SavegameReader, SavegameWriter,
+    IGetCdKeysListener, IGetLoginDetailsListener, IGetSavegameListListener,
+    IGetSavegameReaderListener, IGetSavegameWriterListener, IRemoveSavegameListener,
+    ISavegameReadListener, ISavegameWriteListener,
 };
 
-static ACCOUNT_ID: Lazy<Ustr> = Lazy::new(|| Ustr::from(&CONFIG.orbit.profile.account_id.as_str()));
-static USERNAME: Lazy<Ustr> = Lazy::new(|| Ustr::from(&CONFIG.orbit.profile.username.as_str()));
-static PASSWORD: Lazy<Ustr> = Lazy::new(|| Ustr::from(&CONFIG.orbit.profile.password.as_str()));
-
-macro_rules! thiscall {
-    ($ty: expr, $body: expr) => {
-        set_this_ptr_cxx($ty as u32);
-        $body
-    };
-}
+static ACCOUNT_ID: Lazy<Ustr> = Lazy::new(|| Ustr::from(CONFIG.orbit.profile.account_id.as_str()));
+static USERNAME: Lazy<Ustr> = Lazy::new(|| Ustr::from(CONFIG.orbit.profile.username.as_str()));
+static PASSWORD: Lazy<Ustr> = Lazy::new(|| Ustr::from(CONFIG.orbit.profile.password.as_str()));
 
 #[inline(never)]
 #[cxxabi(name = "??0OrbitClient@orbitclient@mg@@QAE@XZ", ctor = true)]
 fn orbit_client_ctor() -> *const OrbitClient {
     fn_debug!("__CALL__");
-    alloc(OrbitClient::default())
+
+    let (saves_path, result) = match get_saves_path(&CONFIG.orbit) {
+        Ok(saves_path) => (
+            Some(saves_path.clone()),
+            open_storage(&CONFIG.orbit, saves_path),
+        ),
+        Err(err) => (None, Err(err)),
+    };
+
+    // Saves the game could not keep must not look like they were kept.
+    let storage_opened = result.is_ok();
+    let storage: Rc<dyn SaveStorage> = match result {
+        Ok(storage) => storage,
+        Err(err) => {
+            error!("Could not open the saves: {}", err);
+            Rc::new(UnavailableStorage::new(err.to_string()))
+        }
+    };
+
+    let mut client = OrbitClient::new(
+        storage,
+        CONFIG.orbit.product_id,
+        CONFIG.orbit.product_ids.clone(),
+    );
+
+    client.installation = check_installation(storage_opened, saves_path.as_deref());
+    client.cd_keys = get_cd_keys(&CONFIG.orbit);
+    client.quota = CONFIG.orbit.quota.clone();
+
+    alloc(client)
 }
 
 #[inline(never)]
@@ -51,11 +76,22 @@ fn orbit_client_ctor() -> *const OrbitClient {
 )]
 fn orbit_client_start_process(
     client: *const OrbitClient,
-    unk0: *const u16,
-    unk1: *const u16,
-    unk2: *const u16,
+    path: *mut u16,
+    arguments: *mut u16,
+    working_directory: *mut u16,
 ) {
     fn_debug!("__CALL__");
+
+    let path = unsafe { from_wide_ptr(path) };
+    let arguments = unsafe { from_wide_ptr(arguments) };
+    let working_directory = unsafe { from_wide_ptr(working_directory) };
+
+    api::orbit_client_start_process(
+        &CONFIG.orbit.launcher,
+        path.as_deref(),
+        arguments.as_deref(),
+        working_directory.as_deref(),
+    );
 }
 
 #[inline(never)]
@@ -64,14 +100,25 @@ fn orbit_client_start_process(
     ctor = false
 )]
 fn orbit_client_start_launcher(
-    client: *const OrbitClient,
-    unk0: u32,
-    unk1: u32,
-    unk2: *const c_char,
-    unk3: *const c_char,
+    client: *mut OrbitClient,
+    product_id: u32,
+    flags: u32,
+    language: *const c_char,
+    arguments: *const c_char,
 ) -> bool {
     fn_debug!("__CALL__");
-    return false;
+
+    let language = unsafe { from_c_ptr(language) };
+    let arguments = unsafe { from_c_ptr(arguments) };
+
+    return api::orbit_client_start_launcher(
+        unsafe { &mut (*client) },
+        &CONFIG.orbit.launcher,
+        product_id,
+        flags,
+        language,
+        arguments,
+    );
 }
 
 #[inline(never)]
@@ -93,40 +140,13 @@ fn orbit_client_get_savegame_list(
         return;
     }
 
-    let result = || -> Result<Vec<Box<SavegameInfo>>> {
-        let saves = get_saves()?;
-        let mut save_info_list = Vec::new();
-
-        for (id, name, size) in saves {
-            let size = size as u32;
-            let u16name = U16CString::from_str(name)?;
-
-            save_info_list.push(Box::new(SavegameInfo {
-                id,
-                size,
-                name: u16name,
-            }));
-        }
-
-        Ok(save_info_list)
-    }();
-
-    match result {
-        Ok(list) => unsafe {
-            let saves = list.as_ptr();
-            let size = list.len() as u32;
-
-            if size == 0 {
-                thiscall!(savegame_list_listener_callback, {
-                    (*callback)(request_id, ptr::null(), 0);
-                });
-            } else {
-                thiscall!(savegame_list_listener_callback, {
-                    (*callback)(request_id, saves, size as u32);
-                });
-            }
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        api::orbit_client_get_savegame_list(
+            &mut (*client),
+            request_id,
+            &(*savegame_list_listener_callback),
+            product_id,
+        );
     }
 }
 
@@ -151,37 +171,15 @@ fn orbit_client_get_savegame_writer(
         return;
     }
 
-    let result = (|| -> Result<PathBuf> {
-        let path = get_save_path(save_game_id)?;
-        Ok(path)
-    })();
-
-    match result {
-        Ok(file) => unsafe {
-            let client = &mut (*client);
-            let options = if open {
-                OpenOptions::new()
-                    .read(true)
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .to_owned()
-            } else {
-                OpenOptions::new()
-                    .read(true)
-                    .write(true)
-                    .create(true)
-                    .to_owned()
-            };
-            let writer = Box::new(SavegameWriter::new(save_game_id, file, options));
-
-            client.savegame_writer = writer;
-
-            thiscall!(savegame_writer_listener_callback, {
-                (*callback)(request_id, 0, client.savegame_writer.as_ref());
-            });
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        api::orbit_client_get_savegame_writer(
+            &mut (*client),
+            request_id,
+            &(*savegame_writer_listener_callback),
+            product_id,
+            save_game_id,
+            open,
+        );
     }
 }
 
@@ -205,23 +203,14 @@ fn orbit_client_get_savegame_reader(
         return;
     }
 
-    let result = (|| -> Result<PathBuf> {
-        let path = get_save_path(save_game_id)?;
-        Ok(path)
-    })();
-
-    match result {
-        Ok(file) => unsafe {
-            let client = &mut (*client);
-            let reader = Box::new(SavegameReader::new(file));
-
-            client.savegame_reader = reader;
-
-            thiscall!(savegame_reader_listener_callback, {
-                (*callback)(request_id, 0, client.savegame_reader.as_ref());
-            });
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        api::orbit_client_get_savegame_reader(
+            &mut (*client),
+            request_id,
+            &(*savegame_reader_listener_callback),
+            product_id,
+            save_game_id,
+        );
     }
 }
 
@@ -245,18 +234,14 @@ fn orbit_client_remove_savegame(
         return;
     }
 
-    let result = (|| -> Result<()> {
-        remove_save(save_game_id)?;
-        Ok(())
-    })();
-
-    match result {
-        Ok(_) => unsafe {
-            thiscall!(remove_savegame_listener_callback, {
-                (*callback)(request_id, true);
-            });
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        api::orbit_client_remove_savegame(
+            &(*client),
+            request_id,
+            &(*remove_savegame_listener_callback),
+            product_id,
+            save_game_id,
+        );
     }
 }
 
@@ -286,14 +271,43 @@ fn orbit_client_get_login_details(
         thiscall!(login_details_listener_callback, {
             (*callback)(
                 request_id,
-                account_id as *const i8,
-                username as *const i8,
-                password as *const i8,
+                account_id as *const c_char,
+                username as *const c_char,
+                password as *const c_char,
             );
         });
     }
 }
 
+#[inline(never)]
+#[cxxabi(
+    name = "?GetCdKeys@OrbitClient@orbitclient@mg@@QAEXIPAVIGetCdKeysListener@23@I@Z",
+    ctor = false
+)]
+fn orbit_client_get_cd_keys(
+    client: *const OrbitClient,
+    request_id: u32,
+    cd_keys_listener_callback: *const IGetCdKeysListener,
+    product_id: u32,
+) {
+    fn_debug!("__CALL__");
+
+    let callback = unsafe { (*cd_keys_listener_callback).callback };
+
+    if callback.is_null() {
+        return;
+    }
+
+    unsafe {
+        api::orbit_client_get_cd_keys(
+            &(*client),
+            request_id,
+            &(*cd_keys_listener_callback),
+            product_id,
+        );
+    }
+}
+
 #[inline(never)]
 #[cxxabi(
     name = "?GetRequestUniqueId@OrbitClient@orbitclient@mg@@QAEIXZ",
@@ -303,7 +317,7 @@ fn orbit_client_get_request_unique_id(client: *mut OrbitClient) -> u32 {
     fn_debug!("__CALL__");
 
     unsafe {
-        return (*client).get_next_request_id();
+        return api::orbit_client_get_request_unique_id(&mut (*client));
     }
 }
 
@@ -312,9 +326,12 @@ fn orbit_client_get_request_unique_id(client: *mut OrbitClient) -> u32 {
     name = "?GetInstallationErrorNum@OrbitClient@orbitclient@mg@@QAEIXZ",
     ctor = false
 )]
-fn orbit_client_get_installation_error_string(client: *const OrbitClient) -> u16 {
+fn orbit_client_get_installation_error_num(client: *const OrbitClient) -> u32 {
     fn_debug!("__CALL__");
-    return 0;
+
+    let client = unsafe { &(*client) };
+
+    return client.installation.error_num();
 }
 
 #[inline(never)]
@@ -322,15 +339,27 @@ fn orbit_client_get_installation_error_string(client: *const OrbitClient) -> u16
     name = "?GetInstallationErrorString@OrbitClient@orbitclient@mg@@QAEPAGPBD@Z",
     ctor = false
 )]
-fn orbit_client_get_installation_error_num(client: *const OrbitClient) -> *const u16 {
+fn orbit_client_get_installation_error_string(
+    client: *const OrbitClient,
+    locale: *const c_char,
+) -> *const u16 {
     fn_debug!("__CALL__");
-    return ptr::null();
+
+    let locale = unsafe { from_c_ptr(locale) };
+
+    let client = unsafe { &(*client) };
+
+    return client.installation.error_string(locale).as_ptr();
 }
 
 #[inline(never)]
 #[cxxabi(name = "?Update@OrbitClient@orbitclient@mg@@QAEXXZ", ctor = false)]
-fn orbit_client_update(client: *const OrbitClient) {
+fn orbit_client_update(client: *mut OrbitClient) {
     fn_debug!("__CALL__");
+
+    unsafe {
+        api::orbit_client_update(&mut (*client));
+    }
 }
 
 #[inline(never)]
@@ -339,7 +368,7 @@ fn orbit_client_dtor(client: *mut OrbitClient) {
     fn_debug!("__CALL__");
 
     unsafe {
-        Box::from_raw(client);
+        drop(Box::from_raw(client));
     }
 }
 
@@ -397,22 +426,20 @@ fn savegame_reader_read(
         return;
     }
 
-    let reader = unsafe { &(*save_game_reader) };
-
-    let result = (|| -> Result<(Vec<u8>, usize)> {
-        let (data, size) = read_save(&reader.path, number_of_bytes as usize, offset as u64)?;
-        Ok((data, size))
-    })();
-
-    match result {
-        Ok((data, size)) => unsafe {
-            ptr::copy(data.as_ptr() as *const c_char, buffer, size);
-
-            thiscall!(savegame_read_listener_callback, {
-                (*callback)(request_id, size as u32);
-            });
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        let buffer = if buffer.is_null() {
+            &mut []
+        } else {
+            slice::from_raw_parts_mut(buffer as *mut u8, number_of_bytes as usize)
+        };
+
+        api::savegame_reader_read(
+            &(*save_game_reader),
+            request_id,
+            &(*savegame_read_listener_callback),
+            offset,
+            buffer,
+        );
     }
 }
 
@@ -420,6 +447,10 @@ fn savegame_reader_read(
 #[cxxabi(name = "?Close@SavegameReader@orbitclient@mg@@QAEXXZ", ctor = false)]
 fn savegame_reader_close(save_game_reader: *const SavegameReader) {
     fn_debug!("__CALL__");
+
+    unsafe {
+        api::savegame_reader_close(&(*save_game_reader));
+    }
 }
 
 #[inline(never)]
@@ -442,22 +473,29 @@ fn savegame_writer_write(
         return;
     }
 
-    let writer = unsafe { &(*save_game_writer) };
-
-    let result = (|| -> Result<()> {
-        let buffer =
-            unsafe { slice::from_raw_parts(buffer as *const u8, number_of_bytes as usize) };
-        let _ = write_save(&writer.path, &writer.options, buffer)?;
-        Ok(())
-    })();
-
-    match result {
-        Ok(_) => unsafe {
-            thiscall!(savegame_write_listener_callback, {
-                (*callback)(request_id, number_of_bytes as u32);
-            });
-        },
-        Err(err) => error!("{}", err),
+    unsafe {
+        let buffer = if buffer.is_null() {
+            if number_of_bytes > 0 {
+                error!(
+                    "Savegame writer got no buffer for {} bytes",
+                    number_of_bytes
+                );
+                (*savegame_write_listener_callback).callback(request_id, 0);
+
+                return;
+            }
+
+            &[]
+        } else {
+            slice::from_raw_parts(buffer as *const u8, number_of_bytes as usize)
+        };
+
+        api::savegame_writer_write(
+            &(*save_game_writer),
+            request_id,
+            &(*savegame_write_listener_callback),
+            buffer,
+        );
     }
 }
 
@@ -469,38 +507,19 @@ fn savegame_writer_write(
 fn savegame_writer_set_name(save_game_writer: *const SavegameWriter, name: *const u16) -> bool {
     fn_debug!("{:#?}", unsafe { &(*save_game_writer) });
 
-    let writer = unsafe { &(*save_game_writer) };
-
-    let result = (|| -> Result<()> {
-        let u16str = unsafe { U16CStr::from_ptr_str(name) };
-        let u16name = u16str.to_string()?;
-
-        let mut manifest = read_manifest().unwrap_or_default();
-
-        match manifest.saves.iter_mut().find(|save| save.id == writer.id) {
-            Some(save) => {
-                save.name = u16name;
-            }
-            None => manifest.saves.push(Save {
-                id: writer.id,
-                name: u16name,
-            }),
-        }
-
-        let _ = write_manifest(&manifest)?;
-        Ok(())
-    })();
+    unsafe {
+        let name = U16CStr::from_ptr_str(name);
 
-    match result {
-        Ok(_) => return true,
-        Err(err) => error!("{}", err),
+        return api::savegame_writer_set_name(&(*save_game_writer), name);
     }
-
-    return false;
 }
 
 #[inline(never)]
 #[cxxabi(name = "?Close@SavegameWriter@orbitclient@mg@@QAEX_N@Z", ctor = false)]
-fn savegame_writer_close(save_game_writer: *const SavegameWriter) {
+fn savegame_writer_close(save_game_writer: *const SavegameWriter, unk0: bool) {
     fn_debug!("__CALL__");
+
+    unsafe {
+        api::savegame_writer_close(&(*save_game_writer));
+    }
 }
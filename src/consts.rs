@@ -1,3 +0,0 @@
-pub const CONFIG_NAME: &str = "Orbit.toml";
-pub const SAVES_MANIFEST_NAME: &str = "Manifest.toml";
-pub const SAVE_FILE_EXTENSION: &str = "save";
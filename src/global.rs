@@ -1,14 +1,23 @@
-use std::{env, fs};
+use std::env;
 
-use once_cell::sync::Lazy;
+use anyhow::Result;
+use once_cell::sync::{Lazy, OnceCell};
+use orbitcore::consts::CONFIG_NAME;
+use orbitcore::helpers::config::read_config;
+use orbitcore::models::config::Config;
 
-use crate::consts::CONFIG_NAME;
-use crate::models::config::Config;
+pub static CONFIG_ERROR: OnceCell<String> = OnceCell::new();
 
 pub static CONFIG: Lazy<Config> = Lazy::new(|| {
-    let path = env::current_dir().unwrap().join(CONFIG_NAME);
-    let data = fs::read_to_string(path).unwrap();
-    let config: Config = toml::from_str(&data).unwrap();
+    let result = (|| -> Result<Config> {
+        let path = env::current_dir()?.join(CONFIG_NAME);
+        read_config(&path)
+    })();
 
-    config
+    result.unwrap_or_else(|err| {
+        let _ = CONFIG_ERROR.set(err.to_string());
+        Config::default()
+    })
 });
+
+pub static LOG_OPENED: OnceCell<bool> = OnceCell::new();
@@ -1,3 +1,2 @@
 pub mod alloc;
-pub mod manifest;
-pub mod save;
+pub mod string;
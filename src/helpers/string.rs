@@ -0,0 +1,22 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+use widestring::U16CStr;
+
+#[inline]
+pub unsafe fn from_c_ptr<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+#[inline]
+pub unsafe fn from_wide_ptr(ptr: *const u16) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+
+    U16CStr::from_ptr_str(ptr).to_string().ok()
+}
@@ -1,29 +1,66 @@
+macro_rules! thiscall {
+    ($ty: expr, $body: expr) => {
+        set_this_ptr_cxx($ty as u32);
+        $body
+    };
+}
+
 mod api;
-mod consts;
 mod global;
 mod helpers;
-mod models;
 mod types;
 
+use std::env;
 use std::fs::File;
-use std::process::exit;
+use std::path::Path;
 
 use anyhow::Result;
 use ctor::ctor;
-use log::LevelFilter;
+use log::{error, LevelFilter};
+use orbitcore::consts::SANDBOX_ENV_NAME;
+use orbitcore::helpers::config::is_sandbox;
+use orbitcore::installation::Installation;
 use simplelog::{CombinedLogger, ConfigBuilder, WriteLogger};
 
-use crate::global::CONFIG;
+use crate::global::{CONFIG, CONFIG_ERROR, LOG_OPENED};
 
 #[ctor]
 fn init() {
+    let mut log_opened = true;
+
     if CONFIG.orbit.log.write {
         log_panics::init();
+        log_opened = setup_logger().is_ok();
+    }
+
+    let _ = LOG_OPENED.set(log_opened);
+
+    if let Some(err) = CONFIG_ERROR.get() {
+        error!("{}", err);
+    }
+}
+
+/// Checks the installation of a client, once its storage was opened or not.
+/// Read-only and sandbox sessions never write to the saves folder, so it is
+/// not probed for them.
+#[inline]
+fn check_installation(storage_opened: bool, saves_path: Option<&Path>) -> Installation {
+    let storage = &CONFIG.orbit.storage;
+    let sandbox = is_sandbox(storage, env::var_os(SANDBOX_ENV_NAME).as_deref());
+    let probe_path = saves_path.filter(|_| !storage.read_only && !sandbox);
+
+    let installation = Installation::check(
+        CONFIG_ERROR.get().is_none(),
+        storage_opened,
+        probe_path,
+        LOG_OPENED.get().copied().unwrap_or_default(),
+    );
 
-        if setup_logger().is_err() {
-            exit(1);
-        }
+    for err in &installation.errors {
+        error!("Installation error: {:?}", err);
     }
+
+    installation
 }
 
 #[inline]
@@ -1,72 +1,13 @@
-use std::fs::OpenOptions;
-use std::ops::{Deref, DerefMut};
 use std::os::raw::c_char;
-use std::path::PathBuf;
+use std::ptr;
 
-use derive_new::new;
-use widestring::U16CString;
-
-#[derive(Debug, Default)]
-pub struct Inner {
-    pub request_id: u32,
-    pub savegame_reader: Box<SavegameReader>,
-    pub savegame_writer: Box<SavegameWriter>,
-}
-
-#[derive(Debug, Default)]
-pub struct OrbitClient {
-    inner: Box<Inner>,
-}
-
-impl OrbitClient {
-    pub fn get_next_request_id(&mut self) -> u32 {
-        self.inner.request_id += 1;
-        self.inner.request_id
-    }
-}
-
-impl Deref for OrbitClient {
-    type Target = Inner;
-
-    fn deref(&self) -> &Self::Target {
-        &self.inner
-    }
-}
-
-impl DerefMut for OrbitClient {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.inner
-    }
-}
-
-#[derive(Debug, new)]
-pub struct SavegameInfo {
-    pub id: u32,
-    pub size: u32,
-    pub name: U16CString,
-}
-
-#[derive(Debug, Default, new)]
-pub struct SavegameReader {
-    pub path: PathBuf,
-}
-
-#[derive(Debug, new)]
-pub struct SavegameWriter {
-    pub id: u32,
-    pub path: PathBuf,
-    pub options: OpenOptions,
-}
-
-impl Default for SavegameWriter {
-    fn default() -> Self {
-        Self {
-            id: 0,
-            path: Default::default(),
-            options: OpenOptions::new(),
-        }
-    }
-}
+use orbitcore::listeners::{
+    GetCdKeysListener, GetSavegameListListener, GetSavegameReaderListener,
+    GetSavegameWriterListener, RemoveSavegameListener, SavegameReadListener, SavegameWriteListener,
+};
+use orbitcore::types::{SavegameInfo, SavegameReader, SavegameWriter};
+use thiscall::set_this_ptr_cxx;
+use ustr::Ustr;
 
 pub struct IGetSavegameListListener {
     pub callback: *const extern "stdcall" fn(
@@ -79,7 +20,7 @@ pub struct IGetSavegameListListener {
 pub struct IGetSavegameWriterListener {
     pub callback: *const extern "stdcall" fn(
         request_id: u32,
-        unk: u32,
+        error_code: u32,
         savegame_writer: *const SavegameWriter,
     ),
 }
@@ -87,7 +28,7 @@ pub struct IGetSavegameWriterListener {
 pub struct IGetSavegameReaderListener {
     pub callback: *const extern "stdcall" fn(
         request_id: u32,
-        unk: u32,
+        error_code: u32,
         savegame_reader: *const SavegameReader,
     ),
 }
@@ -105,6 +46,14 @@ pub struct IGetLoginDetailsListener {
     ),
 }
 
+pub struct IGetCdKeysListener {
+    pub callback: *const extern "stdcall" fn(
+        request_id: u32,
+        cd_keys: *const *const c_char,
+        cd_keys_size: u32,
+    ),
+}
+
 pub struct ISavegameReadListener {
     pub callback: *const extern "stdcall" fn(request_id: u32, bytes_read: u32),
 }
@@ -112,3 +61,108 @@ pub struct ISavegameReadListener {
 pub struct ISavegameWriteListener {
     pub callback: *const extern "stdcall" fn(request_id: u32, bytes_written: u32),
 }
+
+impl GetSavegameListListener for IGetSavegameListListener {
+    fn callback(&self, request_id: u32, savegame_info_list: &[Box<SavegameInfo>]) {
+        let callback = self.callback;
+
+        unsafe {
+            let saves = savegame_info_list.as_ptr();
+            let size = savegame_info_list.len() as u32;
+
+            if size == 0 {
+                thiscall!(self as *const Self, {
+                    (*callback)(request_id, ptr::null(), 0);
+                });
+            } else {
+                thiscall!(self as *const Self, {
+                    (*callback)(request_id, saves, size);
+                });
+            }
+        }
+    }
+}
+
+impl GetSavegameWriterListener for IGetSavegameWriterListener {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_writer: Option<&SavegameWriter>) {
+        let callback = self.callback;
+        let savegame_writer = savegame_writer.map_or(ptr::null(), |writer| writer as *const _);
+
+        unsafe {
+            thiscall!(self as *const Self, {
+                (*callback)(request_id, error_code, savegame_writer);
+            });
+        }
+    }
+}
+
+impl GetSavegameReaderListener for IGetSavegameReaderListener {
+    fn callback(&self, request_id: u32, error_code: u32, savegame_reader: Option<&SavegameReader>) {
+        let callback = self.callback;
+        let savegame_reader = savegame_reader.map_or(ptr::null(), |reader| reader as *const _);
+
+        unsafe {
+            thiscall!(self as *const Self, {
+                (*callback)(request_id, error_code, savegame_reader);
+            });
+        }
+    }
+}
+
+impl RemoveSavegameListener for IRemoveSavegameListener {
+    fn callback(&self, request_id: u32, removed: bool) {
+        let callback = self.callback;
+
+        unsafe {
+            thiscall!(self as *const Self, {
+                (*callback)(request_id, removed);
+            });
+        }
+    }
+}
+
+impl SavegameReadListener for ISavegameReadListener {
+    fn callback(&self, request_id: u32, bytes_read: u32) {
+        let callback = self.callback;
+
+        unsafe {
+            thiscall!(self as *const Self, {
+                (*callback)(request_id, bytes_read);
+            });
+        }
+    }
+}
+
+impl SavegameWriteListener for ISavegameWriteListener {
+    fn callback(&self, request_id: u32, bytes_written: u32) {
+        let callback = self.callback;
+
+        unsafe {
+            thiscall!(self as *const Self, {
+                (*callback)(request_id, bytes_written);
+            });
+        }
+    }
+}
+
+impl GetCdKeysListener for IGetCdKeysListener {
+    fn callback(&self, request_id: u32, cd_keys: &[Ustr]) {
+        let callback = self.callback;
+
+        unsafe {
+            // `Ustr` is a transparent pointer to its interned C string.
+            let keys = cd_keys.as_ptr() as *const *const c_char;
+            let size = cd_keys.len() as u32;
+
+            if size == 0 {
+                thiscall!(self as *const Self, {
+                    (*callback)(request_id, ptr::null(), 0);
+                });
+            } else {
+                thiscall!(self as *const Self, {
+                    (*callback)(request_id, keys, size);
+                });
+            }
+        }
+    }
+}